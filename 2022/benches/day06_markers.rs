@@ -0,0 +1,43 @@
+//! Day 6's marker finders on a generated stream, so the timings don't depend on anyone's
+//! puzzle input. Run with `cargo bench --bench day06_markers`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// The whole bin comes along, including its `main` and its tests, which go unused here. Its
+// lints are reported when it's built as a bin.
+#[allow(dead_code, unused_imports, non_upper_case_globals, clippy::all)]
+#[path = "../src/day06.rs"]
+mod day06;
+
+use day06::solutions::*;
+
+type Solver = fn(&str) -> usize;
+
+/// About 4 KB cycling through 13 letters, so the first window of 14 different letters is
+/// the very last one.
+fn stream() -> String {
+    "abcdefghijklm".repeat(315) + "n"
+}
+
+fn bench(c: &mut Criterion) {
+    let input = stream();
+    let part2: [(&str, Solver); 5] = [
+        ("devec", solve_part2_devec),
+        ("itertools", solve_part2_window),
+        ("bitbang", solve_part2_bitbang),
+        ("counts", solve_part2_counts),
+        ("xor", solve_part2_xor),
+    ];
+    for (_, solver) in part2 {
+        assert_eq!(solver(&input), input.len());
+    }
+
+    let mut group = c.benchmark_group("day06 part 2");
+    for (name, solver) in part2 {
+        group.bench_function(name, |b| b.iter(|| solver(black_box(&input))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
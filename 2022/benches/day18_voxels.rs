@@ -0,0 +1,50 @@
+//! Day 18's exterior-area solvers on a generated droplet, so the timings don't depend on
+//! anyone's puzzle input. Run with `cargo bench --bench day18_voxels`.
+
+use std::fmt::Write;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// The whole bin comes along, including its `main` and its tests, which go unused here.
+#[allow(dead_code, unused_imports, non_upper_case_globals)]
+#[path = "../src/day18.rs"]
+mod day18;
+
+use day18::solutions::*;
+
+/// A ball of radius 10 in a 22-voxel box like the puzzle's, with a regular scatter of voxels
+/// left out so that there are sealed pockets as well as dents.
+fn droplet() -> String {
+    let mut input = String::new();
+    for (x, y, z) in
+        (0..22).flat_map(|x| (0..22).flat_map(move |y| (0..22).map(move |z| (x, y, z))))
+    {
+        let in_ball = (x - 11) * (x - 11) + (y - 11) * (y - 11) + (z - 11) * (z - 11) <= 100;
+        if in_ball && (x * 7 + y * 11 + z * 13) % 6 != 0 {
+            writeln!(input, "{},{},{}", x, y, z).unwrap();
+        }
+    }
+    input
+}
+
+fn bench(c: &mut Criterion) {
+    let cubes = input_generator(&droplet());
+    let exterior = solve_part2_traversal(cubes.clone());
+    assert_eq!(solve_part2_voxels(cubes.clone()), exterior);
+    assert_eq!(solve_part2_bitgrid(cubes.clone()), exterior);
+
+    let mut group = c.benchmark_group("day18 exterior area");
+    group.bench_function("traverse", |b| {
+        b.iter(|| solve_part2_traversal(black_box(cubes.clone())))
+    });
+    group.bench_function("voxels", |b| {
+        b.iter(|| solve_part2_voxels(black_box(cubes.clone())))
+    });
+    group.bench_function("bitgrid", |b| {
+        b.iter(|| solve_part2_bitgrid(black_box(cubes.clone())))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,184 @@
+use std::iter::FromIterator;
+
+/// A circular sequence whose elements can be moved around by offsets, as in "mixing".
+///
+/// Every element keeps the id it was given at construction (its original index), so callers
+/// can move "the element that started third" without searching for it. Ids are stored in
+/// chunks of about `sqrt(n)`, which makes finding an element's position and moving it
+/// `O(sqrt(n))` instead of the `O(n)` of a plain `Vec`.
+#[derive(Debug, Clone)]
+pub struct CircularList<T> {
+    values: Vec<T>,
+    chunks: Vec<Vec<usize>>,
+    /// Which chunk each id is in.
+    chunk_of: Vec<usize>,
+    chunk_size: usize,
+}
+
+impl<T> CircularList<T> {
+    pub fn new(values: Vec<T>) -> CircularList<T> {
+        let chunk_size = ((values.len() as f64).sqrt() as usize).max(16);
+        let mut list = CircularList {
+            chunk_of: vec![0; values.len()],
+            values,
+            chunks: vec![],
+            chunk_size,
+        };
+        list.rebuild((0..list.values.len()).collect());
+        list
+    }
+
+    fn rebuild(&mut self, order: Vec<usize>) {
+        self.chunks = order.chunks(self.chunk_size).map(|c| c.to_vec()).collect();
+        if self.chunks.is_empty() {
+            self.chunks.push(vec![]);
+        }
+        for (c, chunk) in self.chunks.iter().enumerate() {
+            for &id in chunk {
+                self.chunk_of[id] = c;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The element with the given id, wherever it is now.
+    pub fn get(&self, id: usize) -> &T {
+        &self.values[id]
+    }
+
+    /// Current position of an element, counted from the start of the underlying sequence.
+    pub fn position(&self, id: usize) -> usize {
+        let c = self.chunk_of[id];
+        let before: usize = self.chunks[..c].iter().map(Vec::len).sum();
+        before + self.chunks[c].iter().position(|&i| i == id).unwrap()
+    }
+
+    /// Id of the element at a position, wrapping around.
+    pub fn id_at(&self, pos: usize) -> usize {
+        let mut pos = pos % self.len();
+        for chunk in &self.chunks {
+            if pos < chunk.len() {
+                return chunk[pos];
+            }
+            pos -= chunk.len();
+        }
+        unreachable!("position within length but past the last chunk")
+    }
+
+    /// The element at a position, wrapping around.
+    pub fn at(&self, pos: usize) -> &T {
+        &self.values[self.id_at(pos)]
+    }
+
+    /// The element `offset` places after element `id`, wrapping around.
+    pub fn after(&self, id: usize, offset: usize) -> &T {
+        self.at(self.position(id) + offset % self.len())
+    }
+
+    /// Take element `id` out and put it back `k` places further along (backwards for negative
+    /// `k`). Since the element itself is out of the circle while moving, offsets are taken
+    /// modulo `n - 1`.
+    pub fn move_by(&mut self, id: usize, k: i64) {
+        let n = self.len();
+        if n <= 1 {
+            return;
+        }
+        let from = self.position(id);
+        let c = self.chunk_of[id];
+        let at = self.chunks[c].iter().position(|&i| i == id).unwrap();
+        self.chunks[c].remove(at);
+
+        let mut to = (from as i64 + k).rem_euclid(n as i64 - 1) as usize;
+        let c = self
+            .chunks
+            .iter()
+            .position(|chunk| {
+                if to <= chunk.len() {
+                    return true;
+                }
+                to -= chunk.len();
+                false
+            })
+            .expect("insert position past the end of the list");
+        self.chunks[c].insert(to, id);
+        self.chunk_of[id] = c;
+        if self.chunks[c].len() > 2 * self.chunk_size {
+            let order = self.iter_ids().collect();
+            self.rebuild(order);
+        }
+    }
+
+    /// Ids in their current order, starting from the start of the underlying sequence.
+    pub fn iter_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.chunks.iter().flatten().copied()
+    }
+
+    /// Elements in their current order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter_ids().map(|id| &self.values[id])
+    }
+}
+
+impl<T> FromIterator<T> for CircularList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        CircularList::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::lcg;
+
+    /// The straightforward `Vec` version of `move_by`.
+    fn move_naive(order: &mut Vec<usize>, id: usize, k: i64) {
+        let from = order.iter().position(|&i| i == id).unwrap();
+        order.remove(from);
+        let to = (from as i64 + k).rem_euclid(order.len() as i64);
+        order.insert(to as usize, id);
+    }
+
+    #[test]
+    fn test_matches_vec() {
+        let mut rand = lcg(99);
+        for n in [2, 3, 7, 50, 300] {
+            let mut list: CircularList<usize> = (0..n).collect();
+            let mut naive: Vec<usize> = (0..n).collect();
+            for _ in 0..2000 {
+                let id = rand(n as u64) as usize;
+                let k = rand(2000) as i64 - 1000;
+                list.move_by(id, k);
+                move_naive(&mut naive, id, k);
+            }
+            assert_eq!(list.iter_ids().collect::<Vec<_>>(), naive, "n = {}", n);
+            for (pos, &id) in naive.iter().enumerate() {
+                assert_eq!(list.position(id), pos);
+                assert_eq!(list.id_at(pos + n), id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_lists() {
+        let empty: CircularList<i32> = CircularList::new(vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+
+        let mut one: CircularList<char> = "a".chars().collect();
+        one.move_by(0, 5);
+        assert_eq!(*one.after(0, 3), 'a');
+
+        let mut list: CircularList<char> = "abc".chars().collect();
+        list.move_by(0, 1);
+        assert_eq!(list.iter().collect::<String>(), "bac");
+        assert_eq!(*list.after(0, 1), 'c');
+        assert_eq!(*list.get(2), 'c');
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Where a sequence of states `x0, step(x0), step(step(x0)), ...` starts repeating.
+///
+/// States are compared by a key rather than in full, so a state can carry values that keep
+/// growing (a height, a score) while the key captures just what decides future steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<S> {
+    /// Steps before the first state that's part of the cycle.
+    pub start: usize,
+    pub length: usize,
+    /// The states after `0..=start + length` steps. The last one has the same key as
+    /// `history[start]`.
+    pub history: Vec<S>,
+}
+
+impl<S> Cycle<S> {
+    /// The recorded step whose state has the same key as the state after `n` steps.
+    pub fn equivalent_step(&self, n: u64) -> usize {
+        let start = self.start as u64;
+        if n < start {
+            n as usize
+        } else {
+            self.start + ((n - start) % self.length as u64) as usize
+        }
+    }
+
+    /// A state with the same key as the one after `n` steps. Values that drift from cycle to
+    /// cycle are as they were at the equivalent step; use `extrapolate` for those.
+    pub fn state_at(&self, n: u64) -> &S {
+        &self.history[self.equivalent_step(n)]
+    }
+
+    /// `value` after `n` steps, assuming it changes by the same amount every time round the
+    /// cycle, as a tower's height does.
+    pub fn extrapolate(&self, n: u64, value: impl Fn(&S) -> i64) -> i64 {
+        let equivalent = self.equivalent_step(n);
+        if n < self.start as u64 {
+            return value(&self.history[equivalent]);
+        }
+        let per_cycle =
+            value(&self.history[self.start + self.length]) - value(&self.history[self.start]);
+        let cycles = (n - self.start as u64) / self.length as u64;
+        value(&self.history[equivalent]) + cycles as i64 * per_cycle
+    }
+}
+
+fn record<S: Clone>(initial: S, step: impl Fn(&S) -> S, steps: usize) -> Vec<S> {
+    let mut history = vec![initial];
+    for _ in 0..steps {
+        let next = step(history.last().unwrap());
+        history.push(next);
+    }
+    history
+}
+
+/// Remember every key seen in a `HashMap`. Steps each state only once, so `step` may be
+/// stateful (driving a simulation it owns, say), but memory grows with `start + length`.
+/// Loops forever if the keys never repeat.
+pub fn find_cycle_hashed<S, K: Hash + Eq>(
+    initial: S,
+    step: impl FnMut(&S) -> S,
+    key: impl Fn(&S) -> K,
+) -> Cycle<S> {
+    find_cycle_hashed_within(initial, step, key, usize::MAX)
+        .unwrap_or_else(|_| unreachable!("Ran out of steps looking for a cycle"))
+}
+
+/// Like `find_cycle_hashed`, but stops after `steps` steps. If no key has repeated by then,
+/// returns the `steps + 1` states seen instead, so callers that only need the state after
+/// `steps` steps never simulate further than that.
+pub fn find_cycle_hashed_within<S, K: Hash + Eq>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    key: impl Fn(&S) -> K,
+    steps: usize,
+) -> Result<Cycle<S>, Vec<S>> {
+    let mut seen: HashMap<K, usize> = HashMap::new();
+    let mut history = vec![initial];
+    loop {
+        let now = history.len() - 1;
+        if let Some(&start) = seen.get(&key(&history[now])) {
+            return Ok(Cycle {
+                start,
+                length: now - start,
+                history,
+            });
+        }
+        if now == steps {
+            return Err(history);
+        }
+        seen.insert(key(&history[now]), now);
+        let next = step(&history[now]);
+        history.push(next);
+    }
+}
+
+/// Floyd's tortoise and hare: only two states are kept while searching, at the cost of about
+/// three times the steps. The returned `Cycle` still records all `start + length + 1` states,
+/// walking the sequence once more to do it. `step` must be a pure function, since the sequence
+/// is walked more than once from the start.
+pub fn find_cycle_floyd<S: Clone, K: PartialEq>(
+    initial: S,
+    step: impl Fn(&S) -> S,
+    key: impl Fn(&S) -> K,
+) -> Cycle<S> {
+    let mut tortoise = step(&initial);
+    let mut hare = step(&step(&initial));
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    let mut start = 0;
+    tortoise = initial.clone();
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut length = 1;
+    hare = step(&tortoise);
+    while key(&tortoise) != key(&hare) {
+        hare = step(&hare);
+        length += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        history: record(initial, step, start + length),
+    }
+}
+
+/// Brent's algorithm: like Floyd's, but finds the length first with fewer steps. The search
+/// keeps two states and the returned `Cycle` records every state up to the repeat, as with
+/// Floyd's. `step` must be a pure function.
+pub fn find_cycle_brent<S: Clone, K: PartialEq>(
+    initial: S,
+    step: impl Fn(&S) -> S,
+    key: impl Fn(&S) -> K,
+) -> Cycle<S> {
+    let (mut power, mut length) = (1, 1);
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while key(&tortoise) != key(&hare) {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    let mut start = 0;
+    tortoise = initial.clone();
+    hare = initial.clone();
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        history: record(initial, step, start + length),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    HashMap,
+    Floyd,
+    Brent,
+}
+
+/// Find a cycle with the chosen strategy. All three give the same answer for a pure `step`.
+pub fn find_cycle<S: Clone, K: Hash + Eq>(
+    strategy: Strategy,
+    initial: S,
+    step: impl Fn(&S) -> S,
+    key: impl Fn(&S) -> K,
+) -> Cycle<S> {
+    match strategy {
+        Strategy::HashMap => find_cycle_hashed(initial, step, key),
+        Strategy::Floyd => find_cycle_floyd(initial, step, key),
+        Strategy::Brent => find_cycle_brent(initial, step, key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [Strategy; 3] = [Strategy::HashMap, Strategy::Floyd, Strategy::Brent];
+
+    #[test]
+    fn test_modular_sequence() {
+        // x -> x^2 + 1 mod 255 from 3: 3, 10, 101, 2, 5, 26, 167, 95, 101, ...
+        let step = |x: &u32| (x * x + 1) % 255;
+        for strategy in STRATEGIES {
+            let cycle = find_cycle(strategy, 3, step, |x| *x);
+            assert_eq!((cycle.start, cycle.length), (2, 6), "{:?}", strategy);
+            assert_eq!(cycle.history, vec![3, 10, 101, 2, 5, 26, 167, 95, 101]);
+
+            let mut x = 3;
+            for n in 0..40 {
+                assert_eq!(*cycle.state_at(n), x, "{:?} step {}", strategy, n);
+                x = step(&x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extrapolate_drift() {
+        // A counter that gains 10 per lap of a 7-step track, after a 3-step run-up.
+        let step = |&(pos, total): &(u32, i64)| {
+            let next = if pos == 9 { 3 } else { pos + 1 };
+            (next, total + if next == 3 { 10 } else { 1 })
+        };
+        for strategy in STRATEGIES {
+            let cycle = find_cycle(strategy, (0, 0), step, |s| s.0);
+            assert_eq!((cycle.start, cycle.length), (3, 7));
+
+            let mut state = (0, 0);
+            for n in 0..100 {
+                assert_eq!(cycle.extrapolate(n, |s| s.1), state.1, "step {}", n);
+                state = step(&state);
+            }
+            let per_lap = 6 + 10;
+            assert_eq!(
+                cycle.extrapolate(3 + 7 * 1_000_000_000, |s| s.1),
+                12 + per_lap * 1_000_000_000
+            );
+        }
+    }
+
+    #[test]
+    fn test_stateful_step() {
+        let mut calls = 0;
+        let cycle = find_cycle_hashed(
+            0u32,
+            |x| {
+                calls += 1;
+                (x + 1) % 4
+            },
+            |x| *x,
+        );
+        assert_eq!((cycle.start, cycle.length), (0, 4));
+        assert_eq!(calls, 4);
+    }
+
+    #[test]
+    fn test_hashed_within() {
+        let step = |x: &u32| (x * x + 1) % 255;
+        let history = find_cycle_hashed_within(3, step, |x| *x, 5).unwrap_err();
+        assert_eq!(history, vec![3, 10, 101, 2, 5, 26]);
+
+        let cycle = find_cycle_hashed_within(3, step, |x| *x, 8).unwrap();
+        assert_eq!((cycle.start, cycle.length), (2, 6));
+        assert_eq!(cycle, find_cycle_hashed(3, step, |x| *x));
+    }
+}
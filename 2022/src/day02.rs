@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
 use aoc_zen_runner_macros::{aoc, generator, solver};
+use itertools::Itertools;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum GameThrow {
@@ -18,6 +22,280 @@ pub type RoundInput = (GameThrow, SecondColumn);
 
 pub type Round = (GameThrow, GameThrow);
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Outcome {
+    Lose = 0,
+    Draw = 1,
+    Win = 2,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Outcome::Lose => "lose",
+            Outcome::Draw => "draw",
+            Outcome::Win => "win",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A game like rock-paper-scissors with any odd number of throws arranged in a cycle. Each
+/// throw beats the half of the others just before it in the cycle and loses to the half just
+/// after it, so any two different throws have a winner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CyclicGame {
+    pub throws: Vec<String>,
+    /// Points for playing each throw.
+    pub throw_points: Vec<u64>,
+    /// Points for each outcome, indexed by `Outcome as usize`.
+    pub outcome_points: [u64; 3],
+}
+
+impl CyclicGame {
+    /// A game with the puzzle's scoring: 1 point for the first throw, 2 for the second and so
+    /// on, and 0, 3 or 6 for losing, drawing or winning.
+    pub fn new(throws: &[&str]) -> CyclicGame {
+        assert!(
+            throws.len() % 2 == 1,
+            "A cyclic game needs an odd number of throws, not {}",
+            throws.len()
+        );
+        CyclicGame {
+            throws: throws.iter().map(|t| t.to_string()).collect(),
+            throw_points: (1..=throws.len() as u64).collect(),
+            outcome_points: [0, 3, 6],
+        }
+    }
+
+    pub fn rock_paper_scissors() -> CyclicGame {
+        CyclicGame::new(&["Rock", "Paper", "Scissors"])
+    }
+
+    /// Ordered so that each throw beats the two before it.
+    pub fn rock_paper_scissors_lizard_spock() -> CyclicGame {
+        CyclicGame::new(&["Rock", "Spock", "Paper", "Lizard", "Scissors"])
+    }
+
+    pub fn with_points(self, throw_points: Vec<u64>, outcome_points: [u64; 3]) -> CyclicGame {
+        assert_eq!(
+            throw_points.len(),
+            self.throws.len(),
+            "Need points for every throw"
+        );
+        CyclicGame {
+            throw_points,
+            outcome_points,
+            ..self
+        }
+    }
+
+    pub fn throw_count(&self) -> usize {
+        self.throws.len()
+    }
+
+    pub fn throw_named(&self, name: &str) -> Option<usize> {
+        self.throws
+            .iter()
+            .position(|t| t.eq_ignore_ascii_case(name))
+    }
+
+    pub fn outcome(&self, mine: usize, theirs: usize) -> Outcome {
+        let n = self.throw_count();
+        match (mine + n - theirs) % n {
+            0 => Outcome::Draw,
+            d if d <= n / 2 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
+    }
+
+    /// A throw that gets `outcome` against `theirs`. With more than three throws there are
+    /// several; this picks the neighbour in the cycle.
+    pub fn throw_for(&self, theirs: usize, outcome: Outcome) -> usize {
+        let n = self.throw_count();
+        match outcome {
+            Outcome::Lose => (theirs + n - 1) % n,
+            Outcome::Draw => theirs,
+            Outcome::Win => (theirs + 1) % n,
+        }
+    }
+
+    pub fn score(&self, mine: usize, theirs: usize) -> u64 {
+        self.throw_points[mine] + self.outcome_points[self.outcome(mine, theirs) as usize]
+    }
+
+    /// Play every round of a strategy guide the way `strategy` reads it.
+    pub fn analyse(&self, guide: &[(char, char)], strategy: &Strategy) -> Analysis {
+        let rounds: Vec<RoundReport> = guide
+            .iter()
+            .map(|&(first, second)| {
+                let theirs = strategy.opponent_throw(first);
+                let mine = match strategy.response(second) {
+                    Response::Throw(t) => t,
+                    Response::Outcome(o) => self.throw_for(theirs, o),
+                };
+                RoundReport {
+                    theirs,
+                    mine,
+                    outcome: self.outcome(mine, theirs),
+                    score: self.score(mine, theirs),
+                }
+            })
+            .collect();
+        Analysis {
+            total: rounds.iter().map(|r| r.score).sum(),
+            rounds,
+        }
+    }
+
+    /// The reading of the guide's second column as throws, each symbol a different throw,
+    /// that scores the most, and its total. `None` if there are more symbols than throws.
+    pub fn best_mapping(
+        &self,
+        guide: &[(char, char)],
+        opponent: &HashMap<char, usize>,
+    ) -> Option<(Strategy, u64)> {
+        let symbols = guide.iter().map(|r| r.1).unique().sorted().collect_vec();
+        // Only how often each pairing comes up matters, not the order of the rounds.
+        let pairings = guide
+            .iter()
+            .map(|&(first, second)| {
+                let theirs = *opponent
+                    .get(&first)
+                    .unwrap_or_else(|| panic!("Unknown opponent symbol: {:?}", first));
+                let symbol = symbols.iter().position(|&s| s == second).unwrap();
+                (theirs, symbol)
+            })
+            .counts();
+
+        let mut best: Option<(Vec<usize>, u64)> = None;
+        for throws in (0..self.throw_count()).permutations(symbols.len()) {
+            let total = pairings
+                .iter()
+                .map(|(&(theirs, symbol), &count)| {
+                    count as u64 * self.score(throws[symbol], theirs)
+                })
+                .sum();
+            if best.as_ref().is_none_or(|(_, b)| total > *b) {
+                best = Some((throws, total));
+            }
+        }
+
+        best.map(|(throws, total)| {
+            let strategy = Strategy {
+                opponent: opponent.clone(),
+                response: symbols
+                    .iter()
+                    .zip(throws)
+                    .map(|(&s, t)| (s, Response::Throw(t)))
+                    .collect(),
+            };
+            (strategy, total)
+        })
+    }
+
+    pub fn describe(&self, round: &RoundReport) -> String {
+        format!(
+            "{} vs {}: {}, {} points",
+            self.throws[round.mine], self.throws[round.theirs], round.outcome, round.score
+        )
+    }
+}
+
+/// What a symbol in the second column of the guide tells us to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    Throw(usize),
+    Outcome(Outcome),
+}
+
+/// How to read a strategy guide: which throw each symbol in the first column stands for, and
+/// what each symbol in the second column means.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Strategy {
+    pub opponent: HashMap<char, usize>,
+    pub response: HashMap<char, Response>,
+}
+
+impl Strategy {
+    /// The `i`th opponent symbol and the `i`th response symbol both stand for throw `i`.
+    pub fn throws(opponent: &str, response: &str) -> Strategy {
+        Strategy {
+            opponent: Strategy::opponent_throws(opponent),
+            response: Strategy::response_throws(response),
+        }
+    }
+
+    /// The `i`th opponent symbol stands for throw `i`, and the three response symbols say to
+    /// lose, draw or win.
+    pub fn outcomes(opponent: &str, response: &str) -> Strategy {
+        Strategy {
+            opponent: Strategy::opponent_throws(opponent),
+            response: Strategy::response_outcomes(response),
+        }
+    }
+
+    /// An opponent mapping where the `i`th symbol stands for throw `i`.
+    pub fn opponent_throws(symbols: &str) -> HashMap<char, usize> {
+        symbols.chars().zip(0..).collect()
+    }
+
+    /// A response mapping where the `i`th symbol stands for throw `i`.
+    pub fn response_throws(symbols: &str) -> HashMap<char, Response> {
+        symbols
+            .chars()
+            .zip(0..)
+            .map(|(c, t)| (c, Response::Throw(t)))
+            .collect()
+    }
+
+    /// A response mapping where the three symbols say to lose, draw or win.
+    pub fn response_outcomes(symbols: &str) -> HashMap<char, Response> {
+        let outcomes = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+        assert_eq!(symbols.chars().count(), 3, "Need one symbol per outcome");
+        symbols
+            .chars()
+            .zip(outcomes)
+            .map(|(c, o)| (c, Response::Outcome(o)))
+            .collect()
+    }
+
+    fn opponent_throw(&self, symbol: char) -> usize {
+        *self
+            .opponent
+            .get(&symbol)
+            .unwrap_or_else(|| panic!("Unknown opponent symbol: {:?}", symbol))
+    }
+
+    fn response(&self, symbol: char) -> Response {
+        *self
+            .response
+            .get(&symbol)
+            .unwrap_or_else(|| panic!("Unknown response symbol: {:?}", symbol))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundReport {
+    pub theirs: usize,
+    pub mine: usize,
+    pub outcome: Outcome,
+    pub score: u64,
+}
+
+/// How each round of a strategy guide went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub rounds: Vec<RoundReport>,
+    pub total: u64,
+}
+
+impl Analysis {
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.rounds.iter().filter(|r| r.outcome == outcome).count()
+    }
+}
+
 #[aoc(2022, day02)]
 pub mod solutions {
     use super::*;
@@ -113,4 +391,136 @@ pub mod solutions {
     pub fn solve_part2(input: Vec<RoundInput>) -> u64 {
         input.iter().map(interpret_round_p2).map(score).sum()
     }
+
+    /// Each round as its two raw symbols, for the configurable engine.
+    pub type Guide = Vec<(char, char)>;
+
+    #[generator(columns)]
+    pub fn columns_generator(input: &str) -> Guide {
+        input
+            .lines()
+            .filter(|ln| !ln.trim().is_empty())
+            .map(|ln| {
+                let Some((first, second)) = ln.split_whitespace().collect_tuple() else {
+                    panic!("Invalid line as input: {:?}", ln)
+                };
+                let (Ok(first), Ok(second)) = (first.parse(), second.parse()) else {
+                    panic!("Expected one symbol per column: {:?}", ln)
+                };
+                (first, second)
+            })
+            .collect()
+    }
+
+    #[solver(part1, engine)]
+    pub fn solve_part1_engine(input: Guide) -> u64 {
+        CyclicGame::rock_paper_scissors()
+            .analyse(&input, &Strategy::throws("ABC", "XYZ"))
+            .total
+    }
+
+    #[solver(part2, engine)]
+    pub fn solve_part2_engine(input: Guide) -> u64 {
+        CyclicGame::rock_paper_scissors()
+            .analyse(&input, &Strategy::outcomes("ABC", "XYZ"))
+            .total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc_zen_runner_macros::aoc_case;
+
+    use super::solutions::*;
+    use super::*;
+
+    #[aoc_case(15, 12)]
+    const INPUT1: &str = "A Y
+B X
+C Z
+";
+
+    #[test]
+    fn test_rounds() {
+        let game = CyclicGame::rock_paper_scissors();
+        let guide = columns_generator(INPUT1);
+
+        let by_throw = game.analyse(&guide, &Strategy::throws("ABC", "XYZ"));
+        let scores = by_throw.rounds.iter().map(|r| r.score).collect_vec();
+        assert_eq!(scores, [8, 1, 6]);
+        assert_eq!(
+            game.describe(&by_throw.rounds[0]),
+            "Paper vs Rock: win, 8 points"
+        );
+        assert_eq!(
+            [Outcome::Win, Outcome::Lose, Outcome::Draw].map(|o| by_throw.count(o)),
+            [1, 1, 1]
+        );
+
+        let by_outcome = game.analyse(&guide, &Strategy::outcomes("ABC", "XYZ"));
+        let scores = by_outcome.rounds.iter().map(|r| r.score).collect_vec();
+        assert_eq!(scores, [4, 1, 7]);
+        let mixed = Strategy {
+            opponent: Strategy::opponent_throws("CAB"),
+            response: Strategy::response_outcomes("XYZ"),
+        };
+        let scores = game
+            .analyse(&guide, &mixed)
+            .rounds
+            .iter()
+            .map(|r| r.score)
+            .collect_vec();
+        assert_eq!(scores, [5, 2, 8]);
+
+        let (best, total) = game
+            .best_mapping(&guide, &Strategy::opponent_throws("ABC"))
+            .unwrap();
+        assert_eq!(total, 24);
+        assert_eq!(best.response[&'X'], Response::Throw(2));
+        assert_eq!(game.analyse(&guide, &best).total, 24);
+    }
+
+    #[test]
+    fn test_cyclic_games() {
+        let game = CyclicGame::rock_paper_scissors_lizard_spock();
+        let [rock, spock, paper, lizard, scissors] =
+            ["rock", "spock", "paper", "lizard", "scissors"].map(|t| game.throw_named(t).unwrap());
+        for (winner, loser) in [
+            (scissors, paper),
+            (paper, rock),
+            (rock, lizard),
+            (lizard, spock),
+            (spock, scissors),
+            (scissors, lizard),
+            (lizard, paper),
+            (paper, spock),
+            (spock, rock),
+            (rock, scissors),
+        ] {
+            assert_eq!(game.outcome(winner, loser), Outcome::Win);
+            assert_eq!(game.outcome(loser, winner), Outcome::Lose);
+        }
+        for theirs in 0..game.throw_count() {
+            for outcome in [Outcome::Lose, Outcome::Draw, Outcome::Win] {
+                assert_eq!(
+                    game.outcome(game.throw_for(theirs, outcome), theirs),
+                    outcome
+                );
+            }
+        }
+
+        // Only winning counts. Paper is the one throw that beats both rock and Spock, and a
+        // mapping can't use it for both X and Y.
+        let game = game.with_points(vec![0; 5], [0, 0, 1]);
+        let guide = columns_generator("A X\nA Y\nB X\nB Y\nC Z\n");
+        let opponent = Strategy::opponent_throws("ABC");
+        let (best, total) = game.best_mapping(&guide, &opponent).unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(game.analyse(&guide, &best).count(Outcome::Win), 4);
+        assert_eq!(
+            CyclicGame::rock_paper_scissors()
+                .best_mapping(&columns_generator("A W\nA X\nA Y\nA Z"), &opponent),
+            None
+        );
+    }
 }
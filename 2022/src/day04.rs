@@ -1,3 +1,4 @@
+use advent_of_code_2022::intervals::IntervalSet;
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use std::cmp::Ordering;
 
@@ -17,6 +18,11 @@ fn range_overlaps(dl: &DataLine) -> i32 {
     res as i32
 }
 
+fn interval_pair(dl: &DataLine) -> (IntervalSet<u16>, IntervalSet<u16>) {
+    let (a_s, a_e, b_s, b_e) = *dl;
+    ((a_s..=a_e).into(), (b_s..=b_e).into())
+}
+
 #[aoc(2022, day04)]
 pub mod solutions {
     use super::*;
@@ -47,4 +53,39 @@ pub mod solutions {
     pub fn solve_part2(input: Vec<DataLine>) -> i32 {
         input.iter().map(range_overlaps).sum()
     }
+
+    #[solver(part1, intervalset)]
+    pub fn solve_part1_intervals(input: Vec<DataLine>) -> i32 {
+        input
+            .iter()
+            .map(interval_pair)
+            .filter(|(a, b)| {
+                let both = a.intersection(b);
+                both == *a || both == *b
+            })
+            .count() as i32
+    }
+
+    #[solver(part2, intervalset)]
+    pub fn solve_part2_intervals(input: Vec<DataLine>) -> i32 {
+        input
+            .iter()
+            .map(interval_pair)
+            .filter(|(a, b)| !a.intersection(b).is_empty())
+            .count() as i32
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use aoc_zen_runner_macros::aoc_case;
+
+    #[aoc_case(2, 4)]
+    const TEST_IN: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
 }
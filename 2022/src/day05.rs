@@ -1,32 +1,426 @@
+use std::error::Error;
+use std::fmt::Display;
 use std::io;
 
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use itertools::Itertools;
 use regex::Regex;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MoveInfo {
     source_stack: u32,
     dest_stack: u32,
     crate_count: i32,
 }
 
+/// Written the way the puzzle input does, with 1-based stacks.
+impl Display for MoveInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.crate_count,
+            self.source_stack + 1,
+            self.dest_stack + 1
+        )
+    }
+}
+
 pub type CrateStack = Vec<char>;
 
 pub type Board = Vec<CrateStack>;
 
+/// Draw a board in the bracketed layout of the puzzle input, label row included. Every line
+/// is padded to the full width so the drawing parses back to the same board.
+pub fn render_board(brd: &Board) -> String {
+    let height = brd.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for level in (0..height).rev() {
+        let row = brd
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(c) => format!("[{}]", c),
+                None => "   ".to_string(),
+            })
+            .join(" ");
+        out.push_str(&row);
+        out.push('\n');
+    }
+    let labels = (1..=brd.len()).map(|i| format!("{:^3}", i)).join(" ");
+    out.push_str(&labels);
+    out.push('\n');
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrawingErrorKind {
+    /// The last line of the drawing should number the stacks `1 2 3 ...`.
+    BadLabel(String),
+    NoLabels,
+    UnexpectedChar(char),
+    UnclosedCrate,
+    /// The crate's brackets don't sit over exactly one stack label.
+    Misaligned,
+    /// Two crates in one row claim the same stack.
+    SharedStack(usize),
+    /// Nothing underneath the crate in its stack.
+    Floating(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawingError {
+    /// 1-based line and column in the drawing.
+    pub line: usize,
+    pub column: usize,
+    pub kind: DrawingErrorKind,
+}
+
+impl Display for DrawingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DrawingErrorKind::BadLabel(l) => write!(f, "unexpected stack label {:?}", l)?,
+            DrawingErrorKind::NoLabels => write!(f, "label row has no stacks")?,
+            DrawingErrorKind::UnexpectedChar(c) => write!(f, "unexpected {:?}", c)?,
+            DrawingErrorKind::UnclosedCrate => write!(f, "crate is not closed")?,
+            DrawingErrorKind::Misaligned => write!(f, "crate is not under one stack label")?,
+            DrawingErrorKind::SharedStack(s) => write!(f, "second crate in stack {}", s + 1)?,
+            DrawingErrorKind::Floating(s) => write!(f, "crate floats over stack {}", s + 1)?,
+        }
+        write!(f, " at line {}, column {}", self.line, self.column)
+    }
+}
+
+impl Error for DrawingError {}
+
+fn is_label_row(line: &str) -> bool {
+    let mut tokens = line.split_whitespace().peekable();
+    tokens.peek().is_some() && tokens.all(|t| t.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Parse a crate drawing whose last line is the label row. Stacks are found from where the
+/// labels are, so lines may be trimmed or ragged and labels may be any width; a crate belongs
+/// to the one label its `[X]` overlaps.
+pub fn parse_drawing(lines: &[&str]) -> Result<Board, DrawingError> {
+    let Some((label_row, rows)) = lines.split_last() else {
+        return Err(DrawingError {
+            line: 1,
+            column: 1,
+            kind: DrawingErrorKind::NoLabels,
+        });
+    };
+    let label_line = lines.len();
+
+    let mut spans: Vec<(usize, usize)> = vec![];
+    let chars = label_row.chars().collect_vec();
+    let mut col = 0;
+    while col < chars.len() {
+        if chars[col].is_whitespace() {
+            col += 1;
+            continue;
+        }
+        let start = col;
+        while col < chars.len() && !chars[col].is_whitespace() {
+            col += 1;
+        }
+        let label: String = chars[start..col].iter().collect();
+        if label != (spans.len() + 1).to_string() {
+            return Err(DrawingError {
+                line: label_line,
+                column: start + 1,
+                kind: DrawingErrorKind::BadLabel(label),
+            });
+        }
+        spans.push((start, col));
+    }
+    if spans.is_empty() {
+        return Err(DrawingError {
+            line: label_line,
+            column: 1,
+            kind: DrawingErrorKind::NoLabels,
+        });
+    }
+
+    let mut stacks: Board = vec![vec![]; spans.len()];
+    for (level, (line_idx, row)) in rows.iter().enumerate().rev().enumerate() {
+        let error = |column: usize, kind| DrawingError {
+            line: line_idx + 1,
+            column: column + 1,
+            kind,
+        };
+        let chars = row.chars().collect_vec();
+        let mut seen = vec![false; spans.len()];
+        let mut col = 0;
+        while col < chars.len() {
+            match chars[col] {
+                c if c.is_whitespace() => col += 1,
+                '[' => {
+                    if chars.get(col + 2) != Some(&']') || chars[col + 1].is_whitespace() {
+                        return Err(error(col, DrawingErrorKind::UnclosedCrate));
+                    }
+                    let overlapping = spans
+                        .iter()
+                        .positions(|(start, end)| *start < col + 3 && col < *end)
+                        .collect_vec();
+                    let [stack] = overlapping[..] else {
+                        return Err(error(col, DrawingErrorKind::Misaligned));
+                    };
+                    if seen[stack] {
+                        return Err(error(col, DrawingErrorKind::SharedStack(stack)));
+                    }
+                    if stacks[stack].len() != level {
+                        return Err(error(col, DrawingErrorKind::Floating(stack)));
+                    }
+                    seen[stack] = true;
+                    stacks[stack].push(chars[col + 1]);
+                    col += 3;
+                }
+                c => return Err(error(col, DrawingErrorKind::UnexpectedChar(c))),
+            }
+        }
+    }
+    Ok(stacks)
+}
+
 fn print_board(brd: &Board) {
-    println!("Board [");
-    for (i, stack) in brd.iter().enumerate() {
-        let s = stack.iter().join(" ");
-        println!("  {}  {}", i, s);
+    print!("{}", render_board(brd));
+}
+
+fn board_tops(brd: &Board) -> String {
+    brd.iter().filter_map(|s| s.last()).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    NoSuchStack(u32),
+    NotEnoughCrates {
+        stack: u32,
+        have: usize,
+        wanted: i32,
+    },
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::NoSuchStack(s) => write!(f, "there is no stack {}", s + 1),
+            MoveError::NotEnoughCrates {
+                stack,
+                have,
+                wanted,
+            } => write!(
+                f,
+                "stack {} has {} crates, cannot move {}",
+                stack + 1,
+                have,
+                wanted
+            ),
+        }
+    }
+}
+
+/// How a crane lays crates down. Every crane takes the top `crate_count` crates off the source
+/// and puts all of them on the destination; they differ only in the order the crates land.
+pub trait Crane {
+    /// Reorder `crates`, given bottom-to-top as they sat on the source stack, into the
+    /// bottom-to-top order they end up in on the destination.
+    fn arrange(&self, crates: &mut CrateStack);
+}
+
+impl<C: Crane + ?Sized> Crane for Box<C> {
+    fn arrange(&self, crates: &mut CrateStack) {
+        (**self).arrange(crates)
     }
-    println!("]")
+}
+
+/// Moves one crate at a time, so a move reverses the crates (part 1).
+#[derive(Debug, Clone, Copy)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn arrange(&self, crates: &mut CrateStack) {
+        crates.reverse();
+    }
+}
+
+/// Moves all the crates at once, keeping their order (part 2).
+#[derive(Debug, Clone, Copy)]
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn arrange(&self, _crates: &mut CrateStack) {}
+}
+
+/// A 9001 that can only lift `capacity` crates at once, so a big move happens in chunks
+/// taken from the top. With a capacity of 1 it is a 9000.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkedCrane {
+    pub capacity: usize,
+}
+
+impl Crane for ChunkedCrane {
+    fn arrange(&self, crates: &mut CrateStack) {
+        *crates = crates
+            .rchunks(self.capacity.max(1))
+            .flatten()
+            .copied()
+            .collect_vec();
+    }
+}
+
+/// Look a crane up by the name used in puzzle parameters: `9000`, `9001` or `chunked-N`.
+pub fn crane_by_name(name: &str) -> Option<Box<dyn Crane>> {
+    match name {
+        "9000" => Some(Box::new(CrateMover9000)),
+        "9001" => Some(Box::new(CrateMover9001)),
+        _ => {
+            let capacity = name.strip_prefix("chunked-")?.parse().ok()?;
+            (capacity > 0).then(|| Box::new(ChunkedCrane { capacity }) as Box<dyn Crane>)
+        }
+    }
+}
+
+/// A board plus the moves applied to it, with undo and redo.
+///
+/// `history[..position]` have been applied; anything after that was undone and can be
+/// redone. Applying a new move drops the redo tail, like an editor.
+#[derive(Debug, Clone)]
+pub struct CrateSim<C> {
+    board: Board,
+    crane: C,
+    history: Vec<MoveInfo>,
+    position: usize,
+    /// For each applied move, the crates it took, in the order they sat on the source stack.
+    lifted: Vec<CrateStack>,
+}
+
+impl<C: Crane> CrateSim<C> {
+    pub fn new(board: Board, crane: C) -> CrateSim<C> {
+        CrateSim {
+            board,
+            crane,
+            history: vec![],
+            position: 0,
+            lifted: vec![],
+        }
+    }
+
+    /// Start with a whole rearrangement queued up as redo history, before its first move.
+    pub fn with_program(board: Board, moves: &[MoveInfo], crane: C) -> CrateSim<C> {
+        let mut sim = CrateSim::new(board, crane);
+        sim.history = moves.to_vec();
+        sim
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn tops(&self) -> String {
+        board_tops(&self.board)
+    }
+
+    /// Number of moves currently applied.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Every move applied so far, oldest first.
+    pub fn applied(&self) -> &[MoveInfo] {
+        &self.history[..self.position]
+    }
+
+    /// Applied moves followed by any that can still be redone.
+    pub fn history(&self) -> &[MoveInfo] {
+        &self.history
+    }
+
+    pub fn apply(&mut self, mov: MoveInfo) -> Result<(), MoveError> {
+        self.perform(&mov)?;
+        self.history.truncate(self.position);
+        self.history.push(mov);
+        self.position += 1;
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        let mov = &self.history[self.position];
+        let mut lifted = self.lifted.pop().unwrap();
+        let dest = &mut self.board[mov.dest_stack as usize];
+        dest.truncate(dest.len() - lifted.len());
+        self.board[mov.source_stack as usize].append(&mut lifted);
+        true
+    }
+
+    /// Re-apply the next undone move. Errors if the board no longer allows it, which only
+    /// happens when the history was queued with `with_program`.
+    pub fn redo(&mut self) -> Result<bool, MoveError> {
+        if self.position == self.history.len() {
+            return Ok(false);
+        }
+        let mov = self.history[self.position].clone();
+        self.perform(&mov)?;
+        self.position += 1;
+        Ok(true)
+    }
+
+    /// Undo or redo until exactly `k` moves are applied (or the history runs out).
+    pub fn jump_to(&mut self, k: usize) -> Result<(), MoveError> {
+        while self.position > k {
+            self.undo();
+        }
+        while self.position < k && self.redo()? {}
+        Ok(())
+    }
+
+    fn perform(&mut self, mov: &MoveInfo) -> Result<(), MoveError> {
+        let len = self.board.len() as u32;
+        for s in [mov.source_stack, mov.dest_stack] {
+            if s >= len {
+                return Err(MoveError::NoSuchStack(s));
+            }
+        }
+        let source = &mut self.board[mov.source_stack as usize];
+        if mov.crate_count < 0 || mov.crate_count as usize > source.len() {
+            return Err(MoveError::NotEnoughCrates {
+                stack: mov.source_stack,
+                have: source.len(),
+                wanted: mov.crate_count,
+            });
+        }
+        let lifted = source.split_off(source.len() - mov.crate_count as usize);
+        let mut placed = lifted.clone();
+        self.crane.arrange(&mut placed);
+        self.board[mov.dest_stack as usize].append(&mut placed);
+        self.lifted.push(lifted);
+        Ok(())
+    }
+}
+
+puzzle_params! {
+    /// Cranes for the `named` solvers, by `crane_by_name` name.
+    pub struct Params {
+        part1_crane: String = "9000".to_string(),
+        part2_crane: String = "9001".to_string(),
+    }
+}
+
+fn run_named_crane(board: Board, moves: &[MoveInfo], name: &str) -> String {
+    let crane = crane_by_name(name).unwrap_or_else(|| panic!("Unknown crane: '{}'", name));
+    let mut sim = CrateSim::with_program(board, moves, crane);
+    sim.jump_to(moves.len())
+        .unwrap_or_else(|e| panic!("Invalid move: {}", e));
+    sim.tops()
 }
 
 const DEBUG_MOVE: bool = false;
 
-fn run_arrangement(board: &mut Board, moves: &[MoveInfo], do_reverse: bool) {
+fn run_arrangement(board: &mut Board, moves: &[MoveInfo], crane: &dyn Crane) {
     let stdin = io::stdin();
     let num_moves = moves.len();
     for (i, mov) in moves.iter().enumerate() {
@@ -45,9 +439,7 @@ fn run_arrangement(board: &mut Board, moves: &[MoveInfo], do_reverse: bool) {
         let mut i = {
             let stack = board.get_mut(mov.source_stack as usize).unwrap();
             let mut xs = stack.drain((stack.len() - mov.crate_count as usize)..).collect_vec();
-            if do_reverse {
-                xs.reverse();
-            }
+            crane.arrange(&mut xs);
             xs
         };
         {
@@ -72,50 +464,38 @@ pub mod solutions {
     use super::*;
 
     pub type Output = String;
+    pub type NamedData = WithParams<(Board, Vec<MoveInfo>), Params>;
 
     #[generator(regex)]
     pub fn input_generator(input: &str) -> (Board, Vec<MoveInfo>) {
-        let first_line = input.lines().next().unwrap();
-        let expected_stacks = (first_line.len() + 1) / 4;
-        let mut stacks: Board = (0..expected_stacks).map(|_| Vec::new()).collect_vec();
-
-        let mut parsed_board = false;
+        let lines = input.lines().collect_vec();
+        let label_row = lines
+            .iter()
+            .position(|l| is_label_row(l))
+            .unwrap_or_else(|| panic!("No stack label row in input"));
+        let stacks = parse_drawing(&lines[..=label_row])
+            .unwrap_or_else(|e| panic!("Invalid drawing: {}", e));
 
         let mut moves: Vec<MoveInfo> = Vec::new();
         let re = Regex::new(r#"move (\d+) from (\d+) to (\d+)"#).unwrap();
 
-        for line in input.lines() {
-            if line.contains(" 1  ") {
-                parsed_board = true;
-                for stack in stacks.iter_mut() {
-                    stack.reverse();
-                    stack.retain(|c| c != &' ');
-                }
-                continue;
-            }
-            if !parsed_board {
-                for (i, col) in line.chars().chunks(4).into_iter().enumerate() {
-                    let crate_label = col.collect_vec()[1];
-                    stacks[i].push(crate_label);
+        for line in &lines[label_row + 1..] {
+            let match_info = re.captures(line);
+            match match_info {
+                None => {
+                    continue;
                 }
-            } else {
-                let match_info = re.captures(line);
-                match match_info {
-                    None => {
-                        continue;
-                    }
-                    Some(m) => {
-                        let count: i32 = m.get(1).unwrap().as_str().parse().unwrap();
-                        let mut src: u32 = m.get(2).unwrap().as_str().parse().unwrap();
-                        let mut dest: u32 = m.get(3).unwrap().as_str().parse().unwrap();
-                        src -= 1;
-                        dest -= 1;
-                        moves.push(MoveInfo {
-                            source_stack: src,
-                            dest_stack: dest,
-                            crate_count: count,
-                        });
-                    }
+                Some(m) => {
+                    let count: i32 = m.get(1).unwrap().as_str().parse().unwrap();
+                    let mut src: u32 = m.get(2).unwrap().as_str().parse().unwrap();
+                    let mut dest: u32 = m.get(3).unwrap().as_str().parse().unwrap();
+                    src -= 1;
+                    dest -= 1;
+                    moves.push(MoveInfo {
+                        source_stack: src,
+                        dest_stack: dest,
+                        crate_count: count,
+                    });
                 }
             }
         }
@@ -124,10 +504,15 @@ pub mod solutions {
         (stacks, moves)
     }
 
+    #[generator(named)]
+    pub fn named_generator(input: &str) -> NamedData {
+        params::parse_with(input, input_generator)
+    }
+
     #[solver(part1, helperf)]
     pub fn solve_part1(input: (Board, Vec<MoveInfo>)) -> Output {
         let mut board = input.0.iter().cloned().collect_vec();
-        run_arrangement(&mut board, &input.1, true);
+        run_arrangement(&mut board, &input.1, &CrateMover9000);
         let mut result = String::new();
         for stack in board {
             result = result + &stack.last().unwrap().to_string();
@@ -139,7 +524,7 @@ pub mod solutions {
     #[solver(part2, helperf)]
     pub fn solve_part2(input: (Board, Vec<MoveInfo>)) -> Output {
         let mut board = input.0.iter().cloned().collect_vec();
-        run_arrangement(&mut board, &input.1, false);
+        run_arrangement(&mut board, &input.1, &CrateMover9001);
         let mut result = String::new();
         for stack in board {
             result = result + &stack.last().unwrap().to_string();
@@ -147,4 +532,186 @@ pub mod solutions {
 
         result
     }
+
+    #[solver(part1, sim)]
+    pub fn solve_part1_sim(input: (Board, Vec<MoveInfo>)) -> Output {
+        let mut sim = CrateSim::with_program(input.0, &input.1, CrateMover9000);
+        sim.jump_to(input.1.len())
+            .unwrap_or_else(|e| panic!("Invalid move: {}", e));
+        sim.tops()
+    }
+
+    #[solver(part2, sim)]
+    pub fn solve_part2_sim(input: (Board, Vec<MoveInfo>)) -> Output {
+        let mut sim = CrateSim::with_program(input.0, &input.1, CrateMover9001);
+        sim.jump_to(input.1.len())
+            .unwrap_or_else(|e| panic!("Invalid move: {}", e));
+        sim.tops()
+    }
+
+    #[solver(part1, named)]
+    pub fn solve_part1_named(input: NamedData) -> Output {
+        let (board, moves) = input.data;
+        run_named_crane(board, &moves, &input.params.part1_crane)
+    }
+
+    #[solver(part2, named)]
+    pub fn solve_part2_named(input: NamedData) -> Output {
+        let (board, moves) = input.data;
+        run_named_crane(board, &moves, &input.params.part2_crane)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aoc_zen_runner_macros::aoc_case;
+
+    use super::solutions::input_generator;
+    use super::*;
+
+    #[aoc_case("CMZ", "MCD")]
+    const TEST_IN: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    #[test]
+    fn test_render_round_trip() {
+        let (board, moves) = input_generator(TEST_IN);
+        let rendered = format!("{}\n{}\n", render_board(&board), moves.iter().join("\n"));
+        assert_eq!(rendered, TEST_IN);
+
+        let mut sim = CrateSim::with_program(board.clone(), &moves, CrateMover9000);
+        for k in 0..=moves.len() {
+            sim.jump_to(k).unwrap();
+            let (parsed, _) = input_generator(&render_board(sim.board()));
+            assert_eq!(&parsed, sim.board());
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (board, moves) = input_generator(TEST_IN);
+        let mut sim = CrateSim::with_program(board.clone(), &moves, CrateMover9001);
+        sim.jump_to(4).unwrap();
+        assert_eq!(sim.tops(), "MCD");
+        assert_eq!(sim.applied(), &moves[..]);
+
+        sim.jump_to(2).unwrap();
+        let mut step_by_step = CrateSim::new(board.clone(), CrateMover9001);
+        step_by_step.apply(moves[0].clone()).unwrap();
+        step_by_step.apply(moves[1].clone()).unwrap();
+        assert_eq!(sim.board(), step_by_step.board());
+
+        assert!(sim.undo() && sim.undo() && !sim.undo());
+        assert_eq!(sim.board(), &board);
+        assert_eq!(sim.redo(), Ok(true));
+        assert_eq!(sim.position(), 1);
+
+        // A fresh move replaces whatever could have been redone.
+        sim.apply(moves[3].clone()).unwrap();
+        assert_eq!(sim.history(), &[moves[0].clone(), moves[3].clone()]);
+        assert_eq!(sim.redo(), Ok(false));
+
+        let too_many = MoveInfo {
+            source_stack: 2,
+            dest_stack: 0,
+            crate_count: 5,
+        };
+        assert_eq!(
+            sim.apply(too_many),
+            Err(MoveError::NotEnoughCrates {
+                stack: 2,
+                have: 1,
+                wanted: 5
+            })
+        );
+        assert_eq!(sim.position(), 2);
+    }
+
+    #[test]
+    fn test_parse_drawing() {
+        let trimmed = TEST_IN.lines().map(str::trim_end).join("\n");
+        assert_eq!(input_generator(&trimmed), input_generator(TEST_IN));
+
+        let wide: Board = (0..12)
+            .map(|i| (0..i % 4).map(|h| (b'A' + i + h) as char).collect())
+            .collect();
+        let drawing = render_board(&wide);
+        assert!(drawing.ends_with(" 9  10  11  12 \n"));
+        assert_eq!(input_generator(&drawing).0, wide);
+
+        // Labels may be right-aligned instead, and rows ragged.
+        let right_aligned = [
+            "                                     [X]",
+            "[A]                                  [Y]",
+            "  1   2   3   4   5   6   7   8   9  10  11",
+        ]
+        .join("\n");
+        let board = input_generator(&right_aligned).0;
+        assert_eq!(board.len(), 11);
+        assert_eq!(board[0], vec!['A']);
+        assert_eq!(board[9], vec!['Y', 'X']);
+        assert!(board[10].is_empty());
+    }
+
+    #[test]
+    fn test_drawing_errors() {
+        let err = |drawing: &str| parse_drawing(&drawing.lines().collect_vec()).unwrap_err();
+
+        assert_eq!(
+            err("  [A]\n 1   2 "),
+            DrawingError {
+                line: 1,
+                column: 3,
+                kind: DrawingErrorKind::Misaligned
+            }
+        );
+        assert_eq!(
+            err("[A]\n 1   3 ").kind,
+            DrawingErrorKind::BadLabel("3".to_string())
+        );
+        assert_eq!(
+            err("[A]\n\n 1   2 "),
+            DrawingError {
+                line: 1,
+                column: 1,
+                kind: DrawingErrorKind::Floating(0)
+            }
+        );
+        assert_eq!(err("[A] [B\n 1   2 ").kind, DrawingErrorKind::UnclosedCrate);
+        assert_eq!(
+            err("[A] x\n 1   2 ").kind,
+            DrawingErrorKind::UnexpectedChar('x')
+        );
+        assert_eq!(
+            err("[A][B]\n 1  ").to_string(),
+            "crate is not under one stack label at line 1, column 4"
+        );
+    }
+
+    #[test]
+    fn test_cranes() {
+        let mut crates = vec!['a', 'b', 'c', 'd', 'e'];
+        ChunkedCrane { capacity: 2 }.arrange(&mut crates);
+        assert_eq!(crates, vec!['d', 'e', 'b', 'c', 'a']);
+
+        let (board, moves) = input_generator(TEST_IN);
+        assert_eq!(run_named_crane(board.clone(), &moves, "chunked-1"), "CMZ");
+        assert_eq!(run_named_crane(board.clone(), &moves, "chunked-3"), "MCD");
+        assert_eq!(run_named_crane(board.clone(), &moves, "chunked-2"), "MCZ");
+        assert!(crane_by_name("chunked-0").is_none());
+        assert!(crane_by_name("9002").is_none());
+
+        let input = format!("#! part1_crane=9001 part2_crane=chunked-1\n{}", TEST_IN);
+        let data = solutions::named_generator(&input);
+        assert_eq!(solutions::solve_part1_named(data.clone()), "MCD");
+        assert_eq!(solutions::solve_part2_named(data), "CMZ");
+    }
 }
@@ -1,7 +1,146 @@
 use itertools::Itertools;
 use std::collections::VecDeque;
+use std::io::{self, Read};
 use aoc_zen_runner_macros::{aoc, solution};
 
+/// How `Markers` tells whether the last few bytes are all different.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerStrategy {
+    /// Keep a count of each byte value in the window, and of how many values are in it.
+    /// Works for any bytes.
+    Counts,
+    /// Keep one bit per letter, toggled as letters enter and leave the window. The window is
+    /// all different exactly when as many bits are set as there are letters in it. Only
+    /// works for `a` to `z`.
+    XorMask,
+}
+
+/// Every position in a stream where the last `window` bytes are all different, counted as
+/// the number of bytes read so far. Made by `markers`.
+pub struct Markers<R> {
+    reader: R,
+    /// Bytes read from `reader` but not looked at yet are `buf[start..end]`.
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    window: usize,
+    strategy: MarkerStrategy,
+    /// The last `window` bytes, as a ring buffer.
+    recent: Vec<u8>,
+    counts: [u32; 256],
+    distinct: usize,
+    mask: u32,
+    read: usize,
+    failed: bool,
+}
+
+/// Look for markers of `window` different bytes in `reader`, which is read in chunks as the
+/// markers are asked for.
+pub fn markers<R: Read>(reader: R, window: usize, strategy: MarkerStrategy) -> Markers<R> {
+    assert!(window > 0, "A marker needs at least one byte");
+    Markers {
+        reader,
+        buf: vec![0; 4096],
+        start: 0,
+        end: 0,
+        window,
+        strategy,
+        recent: vec![0; window],
+        counts: [0; 256],
+        distinct: 0,
+        mask: 0,
+        read: 0,
+        failed: false,
+    }
+}
+
+impl<R: Read> Markers<R> {
+    fn next_byte(&mut self) -> Option<io::Result<u8>> {
+        while self.start == self.end {
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(n) => (self.start, self.end) = (0, n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.start += 1;
+        Some(Ok(self.buf[self.start - 1]))
+    }
+
+    fn toggle(&mut self, b: u8, entering: bool) {
+        match self.strategy {
+            MarkerStrategy::Counts => {
+                let count = &mut self.counts[b as usize];
+                if entering {
+                    *count += 1;
+                    self.distinct += (*count == 1) as usize;
+                } else {
+                    *count -= 1;
+                    self.distinct -= (*count == 0) as usize;
+                }
+            }
+            MarkerStrategy::XorMask => self.mask ^= 1 << (b - b'a'),
+        }
+    }
+
+    fn all_different(&self) -> bool {
+        match self.strategy {
+            MarkerStrategy::Counts => self.distinct == self.window,
+            MarkerStrategy::XorMask => self.mask.count_ones() as usize == self.window,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<io::Result<usize>> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let b = match self.next_byte()? {
+                Ok(b) => b,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            };
+            if self.strategy == MarkerStrategy::XorMask && !b.is_ascii_lowercase() {
+                self.failed = true;
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "byte {} ({:?}) is not a lowercase letter",
+                        self.read, b as char
+                    ),
+                )));
+            }
+
+            let slot = self.read % self.window;
+            if self.read >= self.window {
+                self.toggle(self.recent[slot], false);
+            }
+            self.recent[slot] = b;
+            self.toggle(b, true);
+            self.read += 1;
+
+            if self.read >= self.window && self.all_different() {
+                return Some(Ok(self.read));
+            }
+        }
+    }
+}
+
+/// Where the first marker of `window` different characters ends.
+pub fn first_marker(input: &str, window: usize, strategy: MarkerStrategy) -> usize {
+    markers(input.trim().as_bytes(), window, strategy)
+        .next()
+        .expect("No marker in the input")
+        .unwrap()
+}
+
 #[aoc(2022, day06)]
 pub mod solutions {
     use super::*;
@@ -25,6 +164,16 @@ pub mod solutions {
         i.next().unwrap()
     }
 
+    #[solution(part1, counts)]
+    pub fn solve_part1_counts(inp: &str) -> usize {
+        first_marker(inp, 4, MarkerStrategy::Counts)
+    }
+
+    #[solution(part1, xor)]
+    pub fn solve_part1_xor(inp: &str) -> usize {
+        first_marker(inp, 4, MarkerStrategy::XorMask)
+    }
+
     #[solution(part2, devec)]
     pub fn solve_part2_devec(inp: &str) -> usize {
         let input = inp.as_bytes();
@@ -166,11 +315,25 @@ pub mod solutions {
             .filter(|x| *x != 0);
         i.next().unwrap()
     }
+
+    #[solution(part2, counts)]
+    pub fn solve_part2_counts(inp: &str) -> usize {
+        first_marker(inp, 14, MarkerStrategy::Counts)
+    }
+
+    #[solution(part2, xor)]
+    pub fn solve_part2_xor(inp: &str) -> usize {
+        first_marker(inp, 14, MarkerStrategy::XorMask)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use aoc_zen_runner_macros::aoc_case;
+    use itertools::Itertools;
+
+    use super::solutions::*;
+    use super::*;
 
     #[aoc_case(7, 19)]
     const input_1: &str = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
@@ -186,4 +349,51 @@ mod test {
 
     #[aoc_case(11, 26)]
     const input_5: &str = "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw";
+
+    #[test]
+    fn test_generic_markers() {
+        let examples = [input_1, input_2, input_3, input_4, input_5];
+        for input in examples {
+            assert_eq!(solve_part1_counts(input), solve_part1(input));
+            assert_eq!(solve_part2_xor(input), solve_part2_window(input));
+
+            for window in 1..=16 {
+                let expected = (window..=input.len())
+                    .filter(|&end| input.as_bytes()[end - window..end].iter().all_unique())
+                    .collect_vec();
+                for strategy in [MarkerStrategy::Counts, MarkerStrategy::XorMask] {
+                    let found = markers(input.as_bytes(), window, strategy)
+                        .collect::<io::Result<Vec<_>>>()
+                        .unwrap();
+                    assert_eq!(found, expected, "{} {} {:?}", input, window, strategy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_marker_stream() {
+        // Any bytes will do when counting.
+        let input: &[u8] = &[0, 1, 0, 255, 1, 2, 2];
+        let found = markers(input, 3, MarkerStrategy::Counts)
+            .map(Result::unwrap)
+            .collect_vec();
+        assert_eq!(found, [4, 5, 6]);
+
+        // Markers across the reader's chunks, and across readers.
+        let long = "abcd".repeat(3000);
+        let count = markers(long.as_bytes(), 4, MarkerStrategy::XorMask).count();
+        assert_eq!(count, long.len() - 3);
+        let joined = "aab".as_bytes().chain("cdd".as_bytes());
+        let found = markers(joined, 4, MarkerStrategy::Counts)
+            .map(Result::unwrap)
+            .collect_vec();
+        assert_eq!(found, [5]);
+
+        let mut xor = markers("abcd\nabcd".as_bytes(), 4, MarkerStrategy::XorMask);
+        assert_eq!(xor.next().unwrap().unwrap(), 4);
+        let err = xor.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(xor.next().is_none());
+    }
 }
@@ -1,5 +1,8 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt::Display;
 
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::aoc;
 use aoc_zen_runner_macros::generator;
 
@@ -13,17 +16,79 @@ pub enum DataLine {
     ListingDirectory(String),
 }
 
-type Path = Vec<String>;
+/// Writes the line back the way it appears in a transcript.
+impl Display for DataLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataLine::CommandCdToRoot => write!(f, "$ cd /"),
+            DataLine::CommandCdUpdir => write!(f, "$ cd .."),
+            DataLine::CommandCdToSubdir(dir) => write!(f, "$ cd {}", dir),
+            DataLine::CommandLs => write!(f, "$ ls"),
+            DataLine::ListingFile(size, name) => write!(f, "{} {}", size, name),
+            DataLine::ListingDirectory(name) => write!(f, "dir {}", name),
+        }
+    }
+}
 
-#[derive(Debug)]
-struct FileInfo {
-    size: u64,
+pub type Path = Vec<String>;
+
+/// Absolute form of a path, e.g. `/a/e`.
+pub fn path_string(p: &[String]) -> String {
+    format!("/{}", p.join("/"))
+}
+
+/// Split an absolute (or root-relative) path like `/a/e` into its components.
+pub fn parse_path(s: &str) -> Path {
+    s.split('/')
+        .filter(|c| !c.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Shell-style match of `name` against `pattern`, where `*` is any run of characters and `?`
+/// any one character.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    pub name: String,
+    pub size: u64,
 }
 
 #[derive(Debug)]
 struct Terminal {
     current_dir: Path,
     fs: Folder,
+    line: usize,
+    anomalies: Vec<Anomaly>,
 }
 
 impl Terminal {
@@ -31,32 +96,94 @@ impl Terminal {
         Terminal {
             current_dir: vec![],
             fs: Folder::new(),
+            line: 0,
+            anomalies: vec![],
         }
     }
+
+    fn report(&mut self, name: Option<&str>, kind: AnomalyKind) {
+        let mut path = self.current_dir.clone();
+        path.extend(name.map(str::to_string));
+        self.anomalies.push(Anomaly {
+            line: self.line,
+            path,
+            kind,
+        });
+    }
 }
 
-#[derive(Debug)]
-struct Folder {
-    subdirs: HashMap<String, Folder>,
-    files: Vec<FileInfo>,
-    size: u64,
+/// Something in a transcript that a real terminal session could not have produced. The replay
+/// recovers from each one by trusting the most recent line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// A file was listed again with a different size; the new size is kept.
+    SizeChanged { old: u64, new: u64 },
+    /// A name listed as a file is now listed as (or entered as) a directory; the file is dropped.
+    FileBecameDir,
+    /// A name listed as a directory is now listed as a file; the directory is dropped.
+    DirBecameFile,
+    /// `cd` into a directory no listing has mentioned; it is created empty.
+    UnknownDir,
+    /// `cd ..` while already at `/`; ignored.
+    UpdirAtRoot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    /// Zero-based index of the offending transcript line.
+    pub line: usize,
+    pub path: Path,
+    pub kind: AnomalyKind,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}: ", self.line + 1, path_string(&self.path))?;
+        match self.kind {
+            AnomalyKind::SizeChanged { old, new } => {
+                write!(f, "file size changed from {} to {}", old, new)
+            }
+            AnomalyKind::FileBecameDir => write!(f, "file is now a directory"),
+            AnomalyKind::DirBecameFile => write!(f, "directory is now a file"),
+            AnomalyKind::UnknownDir => write!(f, "cd into a directory never listed"),
+            AnomalyKind::UpdirAtRoot => write!(f, "cd .. at the root"),
+        }
+    }
+}
+
+/// A directory in the reconstructed filesystem. Subdirectories are keyed by name; `size` is
+/// only meaningful after `compute_size`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folder {
+    pub subdirs: BTreeMap<String, Folder>,
+    pub files: Vec<FileInfo>,
+    pub size: u64,
 }
 
 impl Folder {
     fn new() -> Folder {
         Folder {
-            subdirs: HashMap::new(),
+            subdirs: BTreeMap::new(),
             files: vec![],
             size: 0,
         }
     }
 
-    fn get_path(&self, p: &Path) -> &Folder {
-        let mut current = self;
-        for d in p {
-            current = current.subdirs.get(d).unwrap()
+    /// Replay a transcript from an empty root, with sizes computed.
+    pub fn from_transcript<'a>(lines: impl IntoIterator<Item = &'a DataLine>) -> Folder {
+        Folder::replay(lines).0
+    }
+
+    /// Like `from_transcript`, but also returns everything odd the replay had to recover from.
+    /// Listing the same entry twice is not an anomaly and does not count it twice.
+    pub fn replay<'a>(lines: impl IntoIterator<Item = &'a DataLine>) -> (Folder, Vec<Anomaly>) {
+        let mut t = Terminal::new();
+        for (i, dl) in lines.into_iter().enumerate() {
+            t.line = i;
+            build_fs_step(&mut t, dl);
         }
-        current
+        t.fs.compute_size();
+        (t.fs, t.anomalies)
     }
 
     fn get_path_mut(&mut self, p: &Path) -> &mut Folder {
@@ -67,6 +194,15 @@ impl Folder {
         current
     }
 
+    pub fn dir(&self, p: &[String]) -> Option<&Folder> {
+        p.iter().try_fold(self, |current, d| current.subdirs.get(d))
+    }
+
+    pub fn file(&self, p: &[String]) -> Option<&FileInfo> {
+        let (name, parent) = p.split_last()?;
+        self.dir(parent)?.files.iter().find(|f| &f.name == name)
+    }
+
     fn compute_size(&mut self) {
         for f in self.subdirs.values_mut() {
             f.compute_size();
@@ -82,50 +218,232 @@ impl Folder {
         results.extend(self.subdirs.values().flat_map(|d| d.get_all_folders()));
         results
     }
+
+    /// Every directory below and including this one, with its path relative to this one.
+    pub fn walk(&self) -> Vec<(Path, &Folder)> {
+        let mut results = vec![(vec![], self)];
+        for (name, d) in &self.subdirs {
+            results.extend(d.walk().into_iter().map(|(mut p, f)| {
+                p.insert(0, name.clone());
+                (p, f)
+            }));
+        }
+        results
+    }
+
+    /// Read a directory on disk into the same model, sizes computed. Symlinks are skipped so a
+    /// link back up the tree cannot loop; names that aren't UTF-8 are converted lossily.
+    pub fn scan(root: &std::path::Path) -> std::io::Result<Folder> {
+        let mut folder = Folder::scan_dir(root)?;
+        folder.compute_size();
+        Ok(folder)
+    }
+
+    fn scan_dir(dir: &std::path::Path) -> std::io::Result<Folder> {
+        let mut folder = Folder::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                folder
+                    .subdirs
+                    .insert(name, Folder::scan_dir(&entry.path())?);
+            } else if file_type.is_file() {
+                let size = entry.metadata()?.len();
+                folder.files.push(FileInfo { name, size });
+            }
+        }
+        folder.files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(folder)
+    }
+
+    /// A `$ cd`/`$ ls` session that visits every directory once and rebuilds this tree.
+    pub fn to_transcript(&self) -> Vec<DataLine> {
+        let mut lines = vec![DataLine::CommandCdToRoot];
+        self.transcript_into(&mut lines);
+        lines
+    }
+
+    fn transcript_into(&self, lines: &mut Vec<DataLine>) {
+        lines.push(DataLine::CommandLs);
+        lines.extend(self.subdirs.keys().cloned().map(DataLine::ListingDirectory));
+        lines.extend(
+            self.files
+                .iter()
+                .map(|f| DataLine::ListingFile(f.size, f.name.clone())),
+        );
+        for (name, d) in &self.subdirs {
+            lines.push(DataLine::CommandCdToSubdir(name.clone()));
+            d.transcript_into(lines);
+            lines.push(DataLine::CommandCdUpdir);
+        }
+    }
+
+    /// `du` for a single path: the total size of a directory, or the size of a file.
+    pub fn size_of(&self, path: &str) -> Option<u64> {
+        let p = parse_path(path);
+        self.dir(&p)
+            .map(|d| d.size)
+            .or_else(|| self.file(&p).map(|f| f.size))
+    }
+
+    /// The `n` largest directories as `(absolute path, size)`, biggest first.
+    pub fn largest_dirs(&self, n: usize) -> Vec<(String, u64)> {
+        let mut dirs: Vec<(String, u64)> = self
+            .walk()
+            .into_iter()
+            .map(|(p, d)| (path_string(&p), d.size))
+            .collect();
+        dirs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        dirs.truncate(n);
+        dirs
+    }
+
+    /// Files anywhere below this directory whose name matches a `glob_match` pattern, as
+    /// `(absolute path, size)` in tree order.
+    pub fn find_files(&self, pattern: &str) -> Vec<(String, u64)> {
+        let mut results = vec![];
+        for (p, d) in self.walk() {
+            let mut files: Vec<&FileInfo> = d
+                .files
+                .iter()
+                .filter(|f| glob_match(pattern, &f.name))
+                .collect();
+            files.sort_by(|a, b| a.name.cmp(&b.name));
+            for f in files {
+                let mut path = p.clone();
+                path.push(f.name.clone());
+                results.push((path_string(&path), f.size));
+            }
+        }
+        results
+    }
+
+    fn fmt_tree(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        enum Entry<'a> {
+            Dir(&'a String, &'a Folder),
+            File(&'a FileInfo),
+        }
+
+        let mut entries: Vec<(&str, Entry)> = self
+            .subdirs
+            .iter()
+            .map(|(name, d)| (name.as_str(), Entry::Dir(name, d)))
+            .chain(
+                self.files
+                    .iter()
+                    .map(|fi| (fi.name.as_str(), Entry::File(fi))),
+            )
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let indent = "  ".repeat(depth);
+        for (_, entry) in entries {
+            match entry {
+                Entry::Dir(name, d) => {
+                    writeln!(f, "{}- {} (dir)", indent, name)?;
+                    d.fmt_tree(f, depth + 1)?;
+                }
+                Entry::File(fi) => writeln!(f, "{}- {} (file, size={})", indent, fi.name, fi.size)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints the tree the way the puzzle text does, with the folder as `/`.
+impl Display for Folder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "- / (dir)")?;
+        self.fmt_tree(f, 1)
+    }
 }
 
 fn build_fs_step(term: &mut Terminal, cmd: &DataLine) {
     match cmd {
         DataLine::CommandCdToRoot => term.current_dir = Vec::new(),
         DataLine::CommandCdUpdir => {
-            term.current_dir.pop();
+            if term.current_dir.pop().is_none() {
+                term.report(None, AnomalyKind::UpdirAtRoot);
+            }
         }
         DataLine::CommandCdToSubdir(subdir) => {
-            assert!(
-                term.fs
-                    .get_path(&term.current_dir)
-                    .subdirs
-                    .contains_key(subdir),
-                "Tried to cd into directory not known to exist!"
-            );
+            let dir = term.fs.get_path_mut(&term.current_dir);
+            if !dir.subdirs.contains_key(subdir) {
+                let kind = if dir.files.iter().any(|f| &f.name == subdir) {
+                    AnomalyKind::FileBecameDir
+                } else {
+                    AnomalyKind::UnknownDir
+                };
+                dir.files.retain(|f| &f.name != subdir);
+                dir.subdirs.insert(subdir.clone(), Folder::new());
+                term.report(Some(subdir), kind);
+            }
             term.current_dir.push(subdir.clone());
         }
         DataLine::CommandLs => {}
-        DataLine::ListingFile(size, _name) => {
+        DataLine::ListingFile(size, name) => {
             let dir = term.fs.get_path_mut(&term.current_dir);
-            dir.files.push(FileInfo { size: *size });
+            let mut anomaly = None;
+            if dir.subdirs.remove(name).is_some() {
+                anomaly = Some(AnomalyKind::DirBecameFile);
+            }
+            match dir.files.iter_mut().find(|f| &f.name == name) {
+                Some(f) => {
+                    if f.size != *size {
+                        anomaly = Some(AnomalyKind::SizeChanged {
+                            old: f.size,
+                            new: *size,
+                        });
+                        f.size = *size;
+                    }
+                }
+                None => dir.files.push(FileInfo {
+                    name: name.clone(),
+                    size: *size,
+                }),
+            }
+            if let Some(kind) = anomaly {
+                term.report(Some(name), kind);
+            }
         }
         DataLine::ListingDirectory(name) => {
             let dir = term.fs.get_path_mut(&term.current_dir);
-            dir.subdirs.entry(name.clone()).or_insert(Folder {
-                subdirs: HashMap::new(),
-                files: vec![],
-                size: 0,
-            });
+            let before = dir.files.len();
+            dir.files.retain(|f| &f.name != name);
+            let replaced_file = dir.files.len() != before;
+            dir.subdirs.entry(name.clone()).or_insert_with(Folder::new);
+            if replaced_file {
+                term.report(Some(name), AnomalyKind::FileBecameDir);
+            }
         }
     };
 }
 
+puzzle_params! {
+    pub struct Params {
+        small_dir_limit: u64 = 100_000,
+        disk_size: u64 = 70_000_000,
+        space_needed: u64 = 30_000_000,
+    }
+}
+
 #[aoc(2022, day07)]
 pub mod solutions {
     use aoc_zen_runner_macros::solver;
 
     use super::*;
 
+    pub type GenData = WithParams<Vec<DataLine>, Params>;
     pub type Day7Output = u64;
 
     #[generator(draft)]
-    pub fn day07_generator(input: &str) -> Vec<DataLine> {
+    pub fn day07_generator(input: &str) -> GenData {
+        params::parse_with(input, parse_transcript)
+    }
+
+    pub fn parse_transcript(input: &str) -> Vec<DataLine> {
         let mut results: Vec<DataLine> = Vec::new();
         for line in input.lines() {
             let data = match line.as_bytes()[0] {
@@ -142,9 +460,7 @@ pub mod solutions {
                         }
                     }
                 }
-                b'd' => DataLine::ListingDirectory(
-                    line.split_whitespace().skip(1).next().unwrap().to_string(),
-                ),
+                b'd' => DataLine::ListingDirectory(line.split_once(' ').unwrap().1.to_string()),
                 b'0' | b'1' | b'2' | b'3' | b'4' | b'5' | b'6' | b'7' | b'8' | b'9' => {
                     let (size, name) = line.split_once(" ").unwrap();
                     DataLine::ListingFile(size.parse().unwrap(), name.to_string())
@@ -158,23 +474,13 @@ pub mod solutions {
     }
 
     #[solver(part1, default)]
-    pub fn solve_part1(input: Vec<DataLine>) -> Day7Output {
-        let mut t = Terminal::new();
-        for dl in input {
-            build_fs_step(&mut t, &dl);
-        }
-
-        let Terminal {
-            current_dir: mut _dir,
-            mut fs,
-        } = t;
-
-        fs.compute_size();
+    pub fn solve_part1(input: GenData) -> Day7Output {
+        let fs = Folder::from_transcript(&input.data);
 
         let mut sum = 0u64;
 
         for f in fs.get_all_folders() {
-            if f.size < 100_000 {
+            if f.size < input.params.small_dir_limit {
                 sum += f.size;
             }
         }
@@ -183,21 +489,11 @@ pub mod solutions {
     }
 
     #[solver(part2, draft)]
-    pub fn solve_part2(input: Vec<DataLine>) -> Day7Output {
-        let mut t = Terminal::new();
-        for dl in input {
-            build_fs_step(&mut t, &dl);
-        }
+    pub fn solve_part2(input: GenData) -> Day7Output {
+        let fs = Folder::from_transcript(&input.data);
 
-        let Terminal {
-            current_dir: mut _dir,
-            mut fs,
-        } = t;
-
-        fs.compute_size();
-
-        let free_space = 70000000 - fs.size;
-        let space_needed = 30000000 - free_space;
+        let free_space = input.params.disk_size - fs.size;
+        let space_needed = input.params.space_needed - free_space;
 
         fs.get_all_folders()
             .into_iter()
@@ -212,6 +508,9 @@ pub mod solutions {
 mod test {
     use aoc_zen_runner_macros::aoc_case;
 
+    use super::solutions::parse_transcript;
+    use super::*;
+
     #[aoc_case(95437, 24933642)]
     const test_input1_str: &str = r#"$ cd /
 $ ls
@@ -237,4 +536,150 @@ $ ls
 5626152 d.ext
 7214296 k
 "#;
+
+    const EXPECTED_TREE: &str = "\
+- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+";
+
+    #[test]
+    fn test_named_fs() {
+        let fs = Folder::from_transcript(&parse_transcript(test_input1_str));
+        assert_eq!(fs.to_string(), EXPECTED_TREE);
+
+        assert_eq!(fs.size_of("/"), Some(48381165));
+        assert_eq!(fs.size_of("/a"), Some(94853));
+        assert_eq!(fs.size_of("/a/e/"), Some(584));
+        assert_eq!(fs.size_of("/d/j"), Some(4060174));
+        assert_eq!(fs.size_of("/x"), None);
+
+        assert_eq!(
+            fs.largest_dirs(2),
+            vec![("/".to_string(), 48381165), ("/d".to_string(), 24933642)]
+        );
+        assert_eq!(
+            fs.find_files("*.l*"),
+            vec![
+                ("/a/h.lst".to_string(), 62596),
+                ("/d/d.log".to_string(), 8033020)
+            ]
+        );
+        let single: Vec<String> = fs.find_files("?").into_iter().map(|(p, _)| p).collect();
+        assert_eq!(single, vec!["/a/f", "/a/g", "/a/e/i", "/d/j", "/d/k"]);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("d.*", "d.log"));
+        assert!(glob_match("*.*t", "b.txt"));
+        assert!(glob_match("?", "f"));
+        assert!(!glob_match("?", "h.lst"));
+        assert!(!glob_match("*.dat", "c.dat.bak"));
+    }
+
+    #[test]
+    fn test_replay_anomalies() {
+        let relisted = format!("{}$ cd /\n$ ls\ndir a\n14848514 b.txt\n", test_input1_str);
+        let (fs, anomalies) = Folder::replay(&parse_transcript(&relisted));
+        assert_eq!(fs.size, 48381165);
+        assert!(anomalies.is_empty());
+
+        let odd = "$ cd ..
+$ ls
+dir a
+100 b
+200 a
+150 b
+$ cd b
+$ ls
+5 c
+$ cd ..
+$ cd x
+$ ls
+7 y
+";
+        let (fs, anomalies) = Folder::replay(&parse_transcript(odd));
+        let kinds: Vec<(usize, String, AnomalyKind)> = anomalies
+            .iter()
+            .map(|a| (a.line, path_string(&a.path), a.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, "/".to_string(), AnomalyKind::UpdirAtRoot),
+                (4, "/a".to_string(), AnomalyKind::DirBecameFile),
+                (
+                    5,
+                    "/b".to_string(),
+                    AnomalyKind::SizeChanged { old: 100, new: 150 }
+                ),
+                (6, "/b".to_string(), AnomalyKind::FileBecameDir),
+                (10, "/x".to_string(), AnomalyKind::UnknownDir),
+            ]
+        );
+        assert_eq!(
+            anomalies[2].to_string(),
+            "line 6: /b: file size changed from 100 to 150"
+        );
+        assert_eq!(fs.size_of("/a"), Some(200));
+        assert_eq!(fs.size_of("/b"), Some(5));
+        assert_eq!(fs.size_of("/"), Some(212));
+    }
+
+    #[test]
+    fn test_transcript_round_trip() {
+        let fs = Folder::from_transcript(&parse_transcript(test_input1_str));
+        let transcript: String = fs
+            .to_transcript()
+            .iter()
+            .map(|l| format!("{}\n", l))
+            .collect();
+        assert!(transcript.starts_with("$ cd /\n$ ls\ndir a\ndir d\n14848514 b.txt\n"));
+
+        let (again, anomalies) = Folder::replay(&parse_transcript(&transcript));
+        assert!(anomalies.is_empty());
+        assert_eq!(again.to_string(), fs.to_string());
+        assert_eq!(again.size, fs.size);
+    }
+
+    #[test]
+    fn test_scan_dir() {
+        let root = std::env::temp_dir().join(format!("aoc-day07-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("a/e")).unwrap();
+        std::fs::create_dir_all(root.join("empty dir")).unwrap();
+        std::fs::write(root.join("a/e/i"), vec![0u8; 584]).unwrap();
+        std::fs::write(root.join("a/f g"), vec![0u8; 29]).unwrap();
+        std::fs::write(root.join("b.txt"), vec![0u8; 1000]).unwrap();
+
+        let scanned = Folder::scan(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        let fs = scanned.unwrap();
+
+        assert_eq!(fs.size, 1613);
+        assert_eq!(fs.size_of("/a"), Some(613));
+        assert_eq!(fs.size_of("/empty dir"), Some(0));
+        assert_eq!(fs.find_files("f *"), vec![("/a/f g".to_string(), 29)]);
+
+        let transcript: String = fs
+            .to_transcript()
+            .iter()
+            .map(|l| format!("{}\n", l))
+            .collect();
+        let again = Folder::from_transcript(&parse_transcript(&transcript));
+        assert_eq!(again, fs);
+    }
 }
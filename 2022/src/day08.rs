@@ -72,7 +72,7 @@ pub mod solutions {
         let row = &grid[row_num];
         let cell = row[col_num];
 
-        if row_num == 0 || col_num == 0 || row_num == grid.len() || col_num == row.len() {
+        if row_num == 0 || col_num == 0 || row_num == grid.len() - 1 || col_num == row.len() - 1 {
             return 0;
         }
 
@@ -136,12 +136,99 @@ pub mod solutions {
 
         max_score
     }
+
+    /// What can be seen of and from every tree in the grid.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Survey {
+        /// Whether each tree can be seen from outside the grid.
+        pub visible: Vec<Vec<bool>>,
+        /// Each tree's scenic score.
+        pub scores: Vec<Vec<u64>>,
+    }
+
+    impl Survey {
+        pub fn visible_count(&self) -> u64 {
+            self.visible.iter().flatten().filter(|&&v| v).count() as u64
+        }
+
+        pub fn best_score(&self) -> u64 {
+            self.scores.iter().flatten().copied().max().unwrap_or(0)
+        }
+    }
+
+    /// Survey the grid by walking along every row and column in both directions, keeping a
+    /// stack of the trees not yet hidden behind a taller one. Each tree is pushed and popped
+    /// once per direction, so this is linear in the number of trees.
+    pub fn survey(grid: &[Vec<u8>]) -> Survey {
+        let rows = grid.len();
+        let cols = grid.first().map_or(0, Vec::len);
+        let mut survey = Survey {
+            visible: vec![vec![false; cols]; rows],
+            scores: vec![vec![1; cols]; rows],
+        };
+
+        let mut lines: Vec<Vec<(usize, usize)>> = vec![];
+        for r in 0..rows {
+            lines.push((0..cols).map(|c| (r, c)).collect());
+            lines.push((0..cols).rev().map(|c| (r, c)).collect());
+        }
+        for c in 0..cols {
+            lines.push((0..rows).map(|r| (r, c)).collect());
+            lines.push((0..rows).rev().map(|r| (r, c)).collect());
+        }
+
+        let mut stack: Vec<usize> = vec![];
+        for line in lines {
+            stack.clear();
+            for (i, &(r, c)) in line.iter().enumerate() {
+                let height = grid[r][c];
+                while stack
+                    .last()
+                    .is_some_and(|&j| grid[line[j].0][line[j].1] < height)
+                {
+                    stack.pop();
+                }
+                // Looking back along the line, the view stops at the nearest tree at least as
+                // tall, or at the edge.
+                match stack.last() {
+                    Some(&j) => survey.scores[r][c] *= (i - j) as u64,
+                    None => {
+                        survey.visible[r][c] = true;
+                        survey.scores[r][c] *= i as u64;
+                    }
+                }
+                stack.push(i);
+            }
+        }
+
+        survey
+    }
+
+    pub fn parse_grid(input: &str) -> Vec<Vec<u8>> {
+        input
+            .lines()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.bytes().collect_vec())
+            .collect_vec()
+    }
+
+    #[solution(part1, stacks)]
+    pub fn solve_part1_stacks(input: &str) -> Day8Output {
+        survey(&parse_grid(input)).visible_count()
+    }
+
+    #[solution(part2, stacks)]
+    pub fn solve_part2_stacks(input: &str) -> Day8Output {
+        survey(&parse_grid(input)).best_score()
+    }
 }
 
 #[cfg(test)]
 pub mod test {
     use super::*;
     use super::solutions::*;
+    use advent_of_code_2022::testutil::lcg;
     use aoc_zen_runner_macros::aoc_case;
 
     #[aoc_case(21, 8)]
@@ -168,4 +255,35 @@ pub mod test {
         assert_eq!(scenic_score(&grid, 3, 2), 8);
         assert_eq!(solve_part2(test_in), 8);
     }
+
+    #[test]
+    pub fn test_survey() {
+        let grid = parse_grid(TEST_IN);
+        let result = survey(&grid);
+        assert_eq!(result.visible[1], [true, true, true, false, true]);
+        assert_eq!(result.visible[3], [true, false, true, false, true]);
+        assert_eq!(result.scores[1][2], 4);
+        assert_eq!(result.scores[3][2], 8);
+        assert_eq!(result.scores[0], [0; 5]);
+
+        let mut rand = lcg(8);
+        for _ in 0..50 {
+            let (rows, cols) = (rand(12) + 1, rand(12) + 1);
+            let input = (0..rows)
+                .map(|_| {
+                    (0..cols)
+                        .map(|_| (b'0' + rand(10) as u8) as char)
+                        .collect::<String>()
+                })
+                .join("\n");
+            let grid = parse_grid(&input);
+            let result = survey(&grid);
+            assert_eq!(result.visible_count(), solve_part1(&input), "{}", input);
+            for (r, row) in result.scores.iter().enumerate() {
+                for (c, &score) in row.iter().enumerate() {
+                    assert_eq!(score, scenic_score(&grid, r, c), "{}", input);
+                }
+            }
+        }
+    }
 }
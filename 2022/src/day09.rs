@@ -1,19 +1,112 @@
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use itertools::{Itertools, MinMaxResult};
 use std::collections::HashSet;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Left,
     Right,
     Up,
     Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, -1),
+            Direction::Up => (0, 1),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Movement {
     dir: Direction,
-    count: u8,
+    count: u32,
+}
+
+/// A rope of any number of knots, dragged around by its head. Remembers every position each
+/// knot has been in.
+#[derive(Debug, Clone)]
+pub struct Rope {
+    knots: Vec<(i32, i32)>,
+    visited: Vec<HashSet<(i32, i32)>>,
+}
+
+impl Rope {
+    pub fn new(knot_count: usize) -> Rope {
+        assert!(knot_count > 0, "A rope needs at least one knot");
+        Rope {
+            knots: vec![(0, 0); knot_count],
+            visited: vec![HashSet::from([(0, 0)]); knot_count],
+        }
+    }
+
+    pub fn knots(&self) -> &[(i32, i32)] {
+        &self.knots
+    }
+
+    /// Positions visited by each knot, head first.
+    pub fn visited(&self) -> &[HashSet<(i32, i32)>] {
+        &self.visited
+    }
+
+    pub fn tail_visited(&self) -> &HashSet<(i32, i32)> {
+        self.visited.last().unwrap()
+    }
+
+    /// Move the head one step by `offset` and let the rest of the rope follow. A knot only
+    /// moves once it's no longer touching the one ahead of it, and then moves one step
+    /// towards it in each direction it's off by.
+    pub fn step(&mut self, offset: (i32, i32)) {
+        self.knots[0] = (self.knots[0].0 + offset.0, self.knots[0].1 + offset.1);
+        self.visited[0].insert(self.knots[0]);
+        for idx in 1..self.knots.len() {
+            let ahead = self.knots[idx - 1];
+            let knot = &mut self.knots[idx];
+            let delta: (i32, i32) = (ahead.0 - knot.0, ahead.1 - knot.1);
+            if delta.0.abs() <= 1 && delta.1.abs() <= 1 {
+                // Knots further back can't move either.
+                break;
+            }
+            knot.0 += delta.0.signum();
+            knot.1 += delta.1.signum();
+            self.visited[idx].insert(*knot);
+        }
+    }
+
+    pub fn apply(&mut self, movement: &Movement) {
+        for _ in 0..movement.count {
+            self.step(movement.dir.offset());
+        }
+    }
+}
+
+/// Run all the movements on a fresh rope of `knot_count` knots.
+pub fn simulate(knot_count: usize, movements: &[Movement]) -> Rope {
+    let mut rope = Rope::new(knot_count);
+    for movement in movements {
+        rope.apply(movement);
+    }
+    rope
+}
+
+puzzle_params! {
+    pub struct Params {
+        knot_count: usize = 10,
+    }
 }
 
 // Solution ---------------------------------------------------------
@@ -23,37 +116,34 @@ pub struct Movement {
 pub mod solutions {
     use super::*;
 
-    pub type GenData = Vec<Movement>;
+    pub type GenData = WithParams<Vec<Movement>, Params>;
     pub type OutData = usize;
 
     #[generator(draft)]
     pub fn input_generator(input: &str) -> GenData {
-        let mut results: GenData = Vec::new();
+        params::parse_with(input, parse_movements)
+    }
+
+    pub fn parse_movements(input: &str) -> Vec<Movement> {
+        let mut results: Vec<Movement> = Vec::new();
         for line in input.lines() {
             if line.trim() == "" {
                 continue;
             }
             let (dir_b, count_str) = line.split_once(" ").unwrap();
-            let count: u8 = str::parse::<u8>(count_str).unwrap();
-            let instruction = match dir_b {
-                "R" => Movement {
-                    dir: Direction::Right,
-                    count,
-                },
-                "L" => Movement {
-                    dir: Direction::Left,
-                    count,
-                },
-                "D" => Movement {
-                    dir: Direction::Down,
-                    count,
-                },
-                "U" => Movement {
-                    dir: Direction::Up,
-                    count,
-                },
+            let count = str::parse::<u32>(count_str).unwrap();
+            let dir = match dir_b {
+                "R" => Direction::Right,
+                "L" => Direction::Left,
+                "D" => Direction::Down,
+                "U" => Direction::Up,
+                "UL" => Direction::UpLeft,
+                "UR" => Direction::UpRight,
+                "DL" => Direction::DownLeft,
+                "DR" => Direction::DownRight,
                 _ => panic!("Invalid movement direction: {}", line),
             };
+            let instruction = Movement { dir, count };
             results.push(instruction);
         }
 
@@ -62,40 +152,7 @@ pub mod solutions {
 
     #[solver(part1, draft)]
     pub fn solve_part1(input: GenData) -> OutData {
-        let mut h_loc: (i32, i32) = (0, 0);
-        let mut t_loc: (i32, i32) = (0, 0);
-        let mut tail_coords: HashSet<(i32, i32)> = HashSet::new();
-        tail_coords.insert((0, 0));
-        for Movement { dir, count } in input {
-            let offset = match dir {
-                Direction::Left => (-1, 0),
-                Direction::Right => (1, 0),
-                Direction::Down => (0, -1),
-                Direction::Up => (0, 1),
-            };
-            for _ in 0..count {
-                h_loc = (h_loc.0 + offset.0, h_loc.1 + offset.1);
-                let delta: (i32, i32) = (h_loc.0 - t_loc.0, h_loc.1 - t_loc.1);
-                match (delta.0.abs(), delta.1.abs()) {
-                    (1, 0) | (0, 1) | (1, 1) | (0, 0) => {
-                        continue;
-                    }
-                    (_, 0) => {
-                        t_loc.0 += delta.0.signum();
-                    }
-                    (0, _) => {
-                        t_loc.1 += delta.1.signum();
-                    }
-                    (_, _) => {
-                        t_loc.0 += delta.0.signum();
-                        t_loc.1 += delta.1.signum();
-                    }
-                }
-                tail_coords.insert(t_loc);
-            }
-        }
-
-        tail_coords.len()
+        simulate(2, &input.data).tail_visited().len()
     }
 
     #[allow(unused)]
@@ -130,50 +187,17 @@ pub mod solutions {
 
     #[solver(part2, draft)]
     pub fn solve_part2(input: GenData) -> OutData {
-        const KNOT_COUNT: usize = 10;
-        let mut knots = [(0i32, 0i32); KNOT_COUNT];
-        let mut tail_coords: HashSet<(i32, i32)> = HashSet::new();
-        tail_coords.insert((0, 0));
-        for Movement { dir, count } in input {
-            let offset = match dir {
-                Direction::Left => (-1, 0),
-                Direction::Right => (1, 0),
-                Direction::Down => (0, -1),
-                Direction::Up => (0, 1),
-            };
-            for _ in 0..count {
-                knots[0] = (knots[0].0 + offset.0, knots[0].1 + offset.1);
-                for (last_idx, current_idx) in (0..knots.len()).tuple_windows() {
-                    let old_knot = knots[last_idx];
-                    let knot = knots.get_mut(current_idx).unwrap();
-                    let delta: (i32, i32) = (old_knot.0 - knot.0, old_knot.1 - knot.1);
-                    match (delta.0.abs(), delta.1.abs()) {
-                        (1, 0) | (0, 1) | (1, 1) | (0, 0) => {
-                            continue;
-                        }
-                        (_, 0) => {
-                            knot.0 += delta.0.signum();
-                        }
-                        (0, _) => {
-                            knot.1 += delta.1.signum();
-                        }
-                        (_, _) => {
-                            knot.0 += delta.0.signum();
-                            knot.1 += delta.1.signum();
-                        }
-                    }
-                }
-                tail_coords.insert(knots[KNOT_COUNT - 1]);
-            }
-        }
-
-        tail_coords.len()
+        simulate(input.params.knot_count, &input.data)
+            .tail_visited()
+            .len()
     }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::solutions::*;
+    use super::*;
+    use aoc_common::params::with_params;
 
     #[allow(unused)]
     const TEST_IN: &str = r#"
@@ -209,4 +233,36 @@ U 20
         //assert_eq!(solve_part2(&input_generator(TEST_IN)), 1);
         assert_eq!(solve_part2(input_generator(TEST_IN_2)), 36);
     }
+
+    #[test]
+    pub fn test_knot_count_param() {
+        let input = with_params(Params { knot_count: 2 }, || input_generator(TEST_IN));
+        assert_eq!(solve_part2(input), 13);
+    }
+
+    #[test]
+    pub fn test_every_knot() {
+        let movements = input_generator(TEST_IN_2).data;
+        let rope = simulate(10, &movements);
+        assert_eq!(rope.knots()[0], (-11, 15));
+        assert_eq!(rope.tail_visited().len(), 36);
+        // Knot `i` of a long rope goes exactly where the tail of an `i + 1` knot rope does.
+        for (idx, visited) in rope.visited().iter().enumerate() {
+            assert_eq!(visited, simulate(idx + 1, &movements).tail_visited());
+        }
+    }
+
+    #[test]
+    pub fn test_long_and_diagonal_moves() {
+        let rope = simulate(3, &parse_movements("R 1000\n"));
+        assert_eq!(rope.knots(), [(1000, 0), (999, 0), (998, 0)]);
+        assert_eq!(rope.tail_visited().len(), 999);
+
+        let rope = simulate(2, &parse_movements("UR 3\nDR 2\nDL 1\nUL 1\n"));
+        assert_eq!(rope.knots(), [(3, 1), (4, 1)]);
+        assert_eq!(
+            rope.tail_visited(),
+            &HashSet::from([(0, 0), (1, 1), (2, 2), (3, 2), (4, 1)])
+        );
+    }
 }
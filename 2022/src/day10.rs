@@ -1,3 +1,5 @@
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use itertools::Itertools;
 
@@ -6,15 +8,25 @@ pub enum Instruction {
     Addx(i32),
 }
 
+puzzle_params! {
+    pub struct Params {
+        breakpoints: Vec<i32> = vec![20, 60, 100, 140, 180, 220],
+    }
+}
+
 #[aoc(2022, day10)]
 pub mod solutions {
     use super::*;
 
-    pub type GenData = Vec<Instruction>;
+    pub type GenData = WithParams<Vec<Instruction>, Params>;
     pub type OutData = i32;
 
     #[generator(draft)]
     pub fn input_generator(input: &str) -> GenData {
+        params::parse_with(input, parse_instructions)
+    }
+
+    pub fn parse_instructions(input: &str) -> Vec<Instruction> {
         let mut results = Vec::new();
 
         for line in input.lines() {
@@ -32,7 +44,7 @@ pub mod solutions {
         results
     }
 
-    pub fn convert_instrs_to_register_stream(instrs: GenData) -> Vec<(i32, i32)> {
+    pub fn convert_instrs_to_register_stream(instrs: Vec<Instruction>) -> Vec<(i32, i32)> {
         instrs
             .iter()
             .map(|instr| match instr {
@@ -49,8 +61,8 @@ pub mod solutions {
 
     #[solver(part1, draft)]
     pub fn solve_part1(input: GenData) -> OutData {
-        let reg_stream = convert_instrs_to_register_stream(input);
-        let breakpoints = [20, 60, 100, 140, 180, 220];
+        let reg_stream = convert_instrs_to_register_stream(input.data);
+        let breakpoints = input.params.breakpoints;
         reg_stream
             .windows(2)
             .scan(0, |break_idx, w| {
@@ -76,7 +88,7 @@ pub mod solutions {
 
     #[solver(part2, draft)]
     pub fn solve_part2(input: GenData) -> String {
-        let instr_stream = convert_instrs_to_register_stream(input);
+        let instr_stream = convert_instrs_to_register_stream(input.data);
         let reg_stream = [(1, 1)].iter().chain(instr_stream.iter());
         let mut instr_iter = reg_stream.tuple_windows::<(_, _)>();
         let mut nxt = instr_iter.next();
@@ -1,3 +1,6 @@
+use advent_of_code_2022::cycles::find_cycle_hashed_within;
+use aoc_common::params;
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::aoc;
 use itertools::Itertools;
 #[allow(unused_imports)]
@@ -18,6 +21,13 @@ pub struct Monkey {
     items_inspected: InspectedType,
 }
 
+puzzle_params! {
+    pub struct Params {
+        part1_rounds: u32 = 20,
+        part2_rounds: u32 = 10_000,
+    }
+}
+
 #[aoc(2022, day11)]
 pub mod solutions {
     use aoc_zen_runner_macros::solution;
@@ -111,10 +121,11 @@ pub mod solutions {
 
     #[solution(part1, draft)]
     pub fn solve_part1(input: &str) -> OutData {
+        let (params, input): (Params, &str) = params::load(input);
         let mut monkeys = input_generator(input);
         monkeys.sort_by_key(|m| m.id);
 
-        for round_num in 1..=20 {
+        for round_num in 1..=params.part1_rounds {
             if cfg!(debug_assertions) {
                 println!("{:#^50}", format!("Round {}", round_num));
             }
@@ -203,12 +214,13 @@ pub mod solutions {
 
     #[solution(part2, draft)]
     pub fn solve_part2(input: &str) -> OutData {
+        let (params, input): (Params, &str) = params::load(input);
         let mut monkeys = input_generator(input);
         monkeys.sort_by_key(|m| m.id);
 
         let modulo = monkeys.iter().fold(1, |x, y| x * y.throw_test_val);
 
-        for round_num in 1..=10_000 {
+        for round_num in 1..=params.part2_rounds {
             if cfg!(debug_assertions) {
                 println!("{:#^50}", format!("Round {}", round_num));
             }
@@ -221,12 +233,109 @@ pub mod solutions {
 
         monkeys[0].items_inspected * monkeys[1].items_inspected
     }
+
+    /// One item between the start of a round and the next: which monkey holds it, its worry
+    /// level, and how many times each monkey has inspected it so far.
+    #[derive(Debug, Clone)]
+    pub struct ItemState {
+        monkey: usize,
+        worry: WorryType,
+        inspections: Vec<InspectedType>,
+    }
+
+    /// Play one round for a single item. Monkeys go in order, so an item thrown to a later
+    /// monkey gets inspected again in the same round, and one thrown back waits for the next.
+    fn item_round(
+        monkeys: &[Monkey],
+        state: &ItemState,
+        decrease_worry: bool,
+        modulo: Option<WorryType>,
+    ) -> ItemState {
+        let mut next = state.clone();
+        loop {
+            let monkey = &monkeys[next.monkey];
+            next.inspections[next.monkey] += 1;
+            next.worry = monkey.operation.as_ref()(next.worry);
+            if let Some(base) = modulo {
+                next.worry %= base;
+            }
+            if decrease_worry {
+                next.worry /= 3;
+            }
+            let target = if monkey.throw_test.as_ref()(next.worry) {
+                monkey.target_true
+            } else {
+                monkey.target_false
+            };
+            let same_round = target > next.monkey;
+            next.monkey = target;
+            if !same_round {
+                return next;
+            }
+        }
+    }
+
+    /// Items never affect each other, so follow each one on its own. An item's path repeats
+    /// once it's back at the same monkey with the same worry level, and its inspection counts
+    /// grow by the same amount every time round.
+    pub fn monkey_business_by_item(
+        mut monkeys: Vec<Monkey>,
+        rounds: u32,
+        decrease_worry: bool,
+        modulo: Option<WorryType>,
+    ) -> OutData {
+        monkeys.sort_by_key(|m| m.id);
+        let mut inspected = vec![0; monkeys.len()];
+        for (holder, monkey) in monkeys.iter().enumerate() {
+            for &worry in monkey.items.iter() {
+                let initial = ItemState {
+                    monkey: holder,
+                    worry,
+                    inspections: vec![0; monkeys.len()],
+                };
+                let found = find_cycle_hashed_within(
+                    initial,
+                    |state| item_round(&monkeys, state, decrease_worry, modulo),
+                    |state| (state.monkey, state.worry),
+                    rounds as usize,
+                );
+                for (idx, total) in inspected.iter_mut().enumerate() {
+                    *total += match &found {
+                        Ok(cycle) => {
+                            cycle.extrapolate(rounds as u64, |s| s.inspections[idx] as i64)
+                                as InspectedType
+                        }
+                        Err(history) => history.last().unwrap().inspections[idx],
+                    };
+                }
+            }
+        }
+
+        inspected.sort_unstable_by(|a, b| b.cmp(a));
+        inspected[0] * inspected[1]
+    }
+
+    #[solution(part1, by_item)]
+    pub fn solve_part1_by_item(input: &str) -> OutData {
+        let (params, input): (Params, &str) = params::load(input);
+        monkey_business_by_item(input_generator(input), params.part1_rounds, true, None)
+    }
+
+    #[solution(part2, by_item)]
+    pub fn solve_part2_by_item(input: &str) -> OutData {
+        let (params, input): (Params, &str) = params::load(input);
+        let monkeys = input_generator(input);
+        let modulo = monkeys.iter().fold(1, |x, y| x * y.throw_test_val);
+        monkey_business_by_item(monkeys, params.part2_rounds, false, Some(modulo))
+    }
 }
 
 #[cfg(test)]
 pub mod test {
+    use aoc_common::params::with_params;
     use aoc_zen_runner_macros::aoc_case;
 
+    use super::solutions::*;
     use super::*;
 
     #[aoc_case(10605, 2713310158u128 as InspectedType)]
@@ -260,4 +369,18 @@ Monkey 3:
     If false: throw to monkey 1
 "#;
 
+    #[test]
+    fn test_by_item_matches_rounds() {
+        // Without the modulo, part 1's worry levels overflow long before 1000 rounds.
+        for (part1_rounds, part2_rounds) in [(1, 1), (7, 97), (20, 1000)] {
+            let params = Params {
+                part1_rounds,
+                part2_rounds,
+            };
+            with_params(params, || {
+                assert_eq!(solve_part1_by_item(test_in), solve_part1(test_in));
+                assert_eq!(solve_part2_by_item(test_in), solve_part2(test_in));
+            });
+        }
+    }
 }
@@ -3,118 +3,246 @@ use itertools::Itertools;
 use petgraph::{algo::dijkstra, prelude::*};
 #[allow(unused_imports)]
 use std::cmp::max;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 
 #[aoc(2022, day12)]
 pub mod solutions {
     use super::*;
     pub struct ParseResults {
-        graph: GraphType,
-        starting: NodeIndex<usize>,
-        ending: NodeIndex<usize>,
+        pub graph: GraphType,
+        pub starting: NodeIndex<usize>,
+        pub ending: NodeIndex<usize>,
     }
 
     pub type CellType = char;
-    pub type GraphType = DiGraph<CellType, (), usize>;
+    pub type GraphType = DiGraph<CellType, u64, usize>;
     pub type GenData = ParseResults;
     pub type OutData = u64;
+    pub type Coord = (usize, usize);
 
-    pub fn check_add_edges(
-        graph: &mut GraphType,
-        cell: &CellType,
-        cell_idx: &NodeIndex<usize>,
-        last_cell: &CellType,
-        last_idx: &NodeIndex<usize>,
-    ) {
-        let diff = (*last_cell as i16) - (*cell as i16);
-        let sig = diff.signum();
-        match sig {
-            -1 => {
-                graph.add_edge(*cell_idx, *last_idx, ());
-                if diff == -1 {
-                    graph.add_edge(*last_idx, *cell_idx, ());
-                }
-            }
-            1 => {
-                graph.add_edge(*last_idx, *cell_idx, ());
-                if diff == 1 {
-                    graph.add_edge(*cell_idx, *last_idx, ());
-                }
+    /// How far a single step may climb or drop, and what it costs.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MoveRule {
+        pub max_climb: i16,
+        pub max_drop: Option<i16>,
+        pub cost_per_elevation: u64,
+    }
+
+    impl Default for MoveRule {
+        fn default() -> Self {
+            MoveRule {
+                max_climb: 1,
+                max_drop: None,
+                cost_per_elevation: 0,
             }
-            0 => {
-                graph.add_edge(*cell_idx, *last_idx, ());
-                graph.add_edge(*last_idx, *cell_idx, ());
+        }
+    }
+
+    impl MoveRule {
+        /// The cost of stepping from `from` to `to`, or `None` if the step isn't allowed.
+        pub fn step_cost(&self, from: CellType, to: CellType) -> Option<u64> {
+            let diff = (to as i16) - (from as i16);
+            if diff > self.max_climb {
+                return None;
             }
-            _ => {
-                panic!("signum() returned an unexpected value: {}", sig)
+            if self.max_drop.is_some_and(|max_drop| -diff > max_drop) {
+                return None;
             }
+            Some(1 + self.cost_per_elevation * diff.unsigned_abs() as u64)
         }
     }
 
-    #[generator(draft)]
-    pub fn input_generator(input: &str) -> GenData {
-        let mut results: GraphType = DiGraph::default();
+    #[derive(Debug, Clone)]
+    pub struct HeightMap {
+        cells: Vec<Vec<CellType>>,
+        starting: Coord,
+        ending: Coord,
+    }
 
-        let input = input.trim_start();
+    impl HeightMap {
+        pub fn parse(input: &str) -> HeightMap {
+            let mut cells = input
+                .trim_start()
+                .lines()
+                .map(|ln| ln.chars().collect_vec())
+                .collect_vec();
 
-        let mut input = input
-            .lines()
-            .map(|ln| ln.chars().collect_vec())
-            .collect_vec();
-        let mut indices = input
-            .iter()
-            .map(|ln| ln.iter().map(|_| 0 as usize).collect_vec())
-            .collect_vec();
-
-        let mut starting_coords = (0, 0);
-        let mut ending_coords = (0, 0);
-
-        for (ln_idx, ln) in input.iter_mut().enumerate() {
-            for (col_idx, cell) in ln.iter_mut().enumerate() {
-                if *cell == 'S' {
-                    starting_coords = (ln_idx, col_idx);
-                    *cell = 'a';
-                } else if *cell == 'E' {
-                    ending_coords = (ln_idx, col_idx);
-                    *cell = 'z';
+            let mut starting = (0, 0);
+            let mut ending = (0, 0);
+
+            for (ln_idx, ln) in cells.iter_mut().enumerate() {
+                for (col_idx, cell) in ln.iter_mut().enumerate() {
+                    if *cell == 'S' {
+                        starting = (ln_idx, col_idx);
+                        *cell = 'a';
+                    } else if *cell == 'E' {
+                        ending = (ln_idx, col_idx);
+                        *cell = 'z';
+                    }
                 }
             }
+
+            HeightMap {
+                cells,
+                starting,
+                ending,
+            }
         }
 
-        for (ln_idx, ln) in input.iter().enumerate() {
-            for (col_idx, cell) in ln.iter().enumerate() {
-                let idx = results.add_node(*cell);
-                indices[ln_idx][col_idx] = idx.index();
+        pub fn starting(&self) -> Coord {
+            self.starting
+        }
 
-                if col_idx > 0 {
-                    let last_cell = input[ln_idx][col_idx - 1];
-                    let last_idx = NodeIndex::from(indices[ln_idx][col_idx - 1]);
+        pub fn ending(&self) -> Coord {
+            self.ending
+        }
 
-                    check_add_edges(&mut results, cell, &idx, &last_cell, &last_idx);
-                }
+        pub fn height(&self, (row, col): Coord) -> CellType {
+            self.cells[row][col]
+        }
 
-                if ln_idx > 0 {
-                    let last_cell = input[ln_idx - 1][col_idx];
-                    let last_idx = NodeIndex::from(indices[ln_idx - 1][col_idx]);
+        pub fn positions_of(&self, height: CellType) -> impl Iterator<Item = Coord> + '_ {
+            self.cells.iter().enumerate().flat_map(move |(ln_idx, ln)| {
+                ln.iter()
+                    .enumerate()
+                    .filter(move |(_, cell)| **cell == height)
+                    .map(move |(col_idx, _)| (ln_idx, col_idx))
+            })
+        }
 
-                    check_add_edges(&mut results, cell, &idx, &last_cell, &last_idx);
+        /// Every cell reachable in one step from `pos` under `rule`, with the cost of that step.
+        pub fn neighbors<'a>(
+            &'a self,
+            pos: Coord,
+            rule: &'a MoveRule,
+        ) -> impl Iterator<Item = (Coord, u64)> + 'a {
+            let (row, col) = pos;
+            let from = self.height(pos);
+            [
+                row.checked_sub(1).map(|r| (r, col)),
+                Some((row + 1, col)),
+                col.checked_sub(1).map(|c| (row, c)),
+                Some((row, col + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|(r, c)| self.cells.get(*r).is_some_and(|ln| *c < ln.len()))
+            .filter_map(move |next| {
+                rule.step_cost(from, self.height(next))
+                    .map(|cost| (next, cost))
+            })
+        }
+
+        pub fn to_graph(&self, rule: &MoveRule) -> ParseResults {
+            let mut results: GraphType = DiGraph::default();
+            let mut indices = self
+                .cells
+                .iter()
+                .map(|ln| ln.iter().map(|_| 0usize).collect_vec())
+                .collect_vec();
+
+            for (ln_idx, ln) in self.cells.iter().enumerate() {
+                for (col_idx, cell) in ln.iter().enumerate() {
+                    let idx = results.add_node(*cell);
+                    indices[ln_idx][col_idx] = idx.index();
+
+                    if col_idx > 0 {
+                        let last_cell = self.cells[ln_idx][col_idx - 1];
+                        let last_idx = NodeIndex::from(indices[ln_idx][col_idx - 1]);
+
+                        check_add_edges(&mut results, rule, cell, &idx, &last_cell, &last_idx);
+                    }
+
+                    if ln_idx > 0 {
+                        let last_cell = self.cells[ln_idx - 1][col_idx];
+                        let last_idx = NodeIndex::from(indices[ln_idx - 1][col_idx]);
+
+                        check_add_edges(&mut results, rule, cell, &idx, &last_cell, &last_idx);
+                    }
                 }
             }
+
+            let starting = NodeIndex::from(indices[self.starting.0][self.starting.1]);
+            let ending = NodeIndex::from(indices[self.ending.0][self.ending.1]);
+
+            ParseResults {
+                graph: results,
+                starting,
+                ending,
+            }
         }
+    }
 
-        let starting = NodeIndex::from(indices[starting_coords.0][starting_coords.1]);
-        let ending = NodeIndex::from(indices[ending_coords.0][ending_coords.1]);
+    pub fn check_add_edges(
+        graph: &mut GraphType,
+        rule: &MoveRule,
+        cell: &CellType,
+        cell_idx: &NodeIndex<usize>,
+        last_cell: &CellType,
+        last_idx: &NodeIndex<usize>,
+    ) {
+        if let Some(cost) = rule.step_cost(*cell, *last_cell) {
+            graph.add_edge(*cell_idx, *last_idx, cost);
+        }
+        if let Some(cost) = rule.step_cost(*last_cell, *cell) {
+            graph.add_edge(*last_idx, *cell_idx, cost);
+        }
+    }
 
-        // dbg!(starting_coords);
-        // dbg!(starting);
-        // dbg!(ending_coords);
-        // dbg!(ending);
-        // dbg!(&results);
+    fn manhattan(a: Coord, b: Coord) -> u64 {
+        (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u64
+    }
+
+    /// A* straight on the height map, without building a graph. Every step costs at least 1,
+    /// so the Manhattan distance to `goal` never overestimates. Any number of starting cells
+    /// may be given; the cheapest of them wins.
+    pub fn astar(
+        map: &HeightMap,
+        rule: &MoveRule,
+        starts: impl IntoIterator<Item = Coord>,
+        goal: Coord,
+    ) -> Option<u64> {
+        let mut best: HashMap<Coord, u64> = HashMap::new();
+        let mut frontier = BinaryHeap::new();
+
+        for start in starts {
+            best.insert(start, 0);
+            frontier.push(Reverse((manhattan(start, goal), 0u64, start)));
+        }
+
+        while let Some(Reverse((_, cost, pos))) = frontier.pop() {
+            if pos == goal {
+                return Some(cost);
+            }
+            if best.get(&pos).is_some_and(|b| *b < cost) {
+                continue;
+            }
 
-        ParseResults {
-            graph: results,
-            starting,
-            ending,
+            for (next, step) in map.neighbors(pos, rule) {
+                let next_cost = cost + step;
+                if best.get(&next).is_none_or(|b| next_cost < *b) {
+                    best.insert(next, next_cost);
+                    frontier.push(Reverse((
+                        next_cost + manhattan(next, goal),
+                        next_cost,
+                        next,
+                    )));
+                }
+            }
         }
+
+        None
+    }
+
+    #[generator(draft)]
+    pub fn input_generator(input: &str) -> GenData {
+        HeightMap::parse(input).to_graph(&MoveRule::default())
+    }
+
+    #[generator(grid)]
+    pub fn grid_generator(input: &str) -> HeightMap {
+        HeightMap::parse(input)
     }
 
     #[solver(part1, draft)]
@@ -123,7 +251,7 @@ pub mod solutions {
         let starting_idx = input.starting;
         let ending_idx = input.ending;
 
-        let results = dijkstra(graph, starting_idx, Some(ending_idx), |_| 1u64);
+        let results = dijkstra(graph, starting_idx, Some(ending_idx), |e| *e.weight());
 
         // dbg!(&results);
 
@@ -158,7 +286,7 @@ pub mod solutions {
         // Reverse the edges so that the ending point is where we can start the search
         graph.reverse();
 
-        let search_results = dijkstra(&graph, ending_idx, None, |_| 1u64);
+        let search_results = dijkstra(&graph, ending_idx, None, |e| *e.weight());
 
         possible_starts
             .iter()
@@ -171,11 +299,35 @@ pub mod solutions {
             .min()
             .unwrap_or_else(|| panic!("No possible starts found in search results."))
     }
+
+    #[solver(part1, astar)]
+    pub fn solve_part1_astar(input: HeightMap) -> OutData {
+        astar(
+            &input,
+            &MoveRule::default(),
+            [input.starting()],
+            input.ending(),
+        )
+        .unwrap_or_else(|| panic!("Could not find a path from start to end."))
+    }
+
+    #[solver(part2, astar)]
+    pub fn solve_part2_astar(input: HeightMap) -> OutData {
+        astar(
+            &input,
+            &MoveRule::default(),
+            input.positions_of('a'),
+            input.ending(),
+        )
+        .unwrap_or_else(|| panic!("No possible starts can reach the end."))
+    }
 }
 
 #[cfg(test)]
 pub mod test {
+    use super::solutions::*;
     use aoc_zen_runner_macros::aoc_case;
+    use petgraph::algo::dijkstra;
 
     #[aoc_case(31, 29)]
     const test_in: &str = r#"
@@ -185,4 +337,32 @@ accszExk
 acctuvwj
 abdefghi
 "#;
+
+    #[test]
+    fn test_move_rules() {
+        let map = HeightMap::parse(test_in);
+
+        let free_climb = MoveRule {
+            max_climb: 25,
+            ..Default::default()
+        };
+        assert_eq!(
+            astar(&map, &free_climb, [map.starting()], map.ending()),
+            Some(7)
+        );
+
+        let hiking = MoveRule {
+            max_climb: 2,
+            max_drop: Some(3),
+            cost_per_elevation: 2,
+        };
+        let graph = map.to_graph(&hiking);
+        let from_graph = dijkstra(&graph.graph, graph.starting, Some(graph.ending), |e| {
+            *e.weight()
+        });
+        assert_eq!(
+            astar(&map, &hiking, [map.starting()], map.ending()),
+            from_graph.get(&graph.ending).copied()
+        );
+    }
 }
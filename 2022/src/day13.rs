@@ -1,24 +1,31 @@
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use itertools::{EitherOrBoth, Itertools};
-use serde::{Deserialize, Serialize};
-use serde_json::from_str;
 #[allow(unused_imports)]
 use std::cmp::max;
 use std::{
     cmp::{min, Ordering},
+    error::Error,
     fmt::Display,
+    str::FromStr,
 };
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
-#[serde(untagged)]
-pub enum IntOrList {
-    Int(i8),
-    List(Vec<IntOrList>),
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IntOrList<N = u64> {
+    Int(N),
+    List(Vec<IntOrList<N>>),
 }
 
 use IntOrList::*;
 
-impl Ord for IntOrList {
+puzzle_params! {
+    pub struct Params {
+        explain: bool = false,
+    }
+}
+
+impl<N: Ord + Clone> Ord for IntOrList<N> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         // Rules are quoted from AoC website for reference.
         // Code assumes pairs will be checked against `<=`
@@ -72,57 +79,392 @@ impl Ord for IntOrList {
                right value to [2] (a list containing 2); the result is then found
                by instead comparing [0,0,0] and [2].
             */
-            (Int(s), o @ List(_)) => List(vec![Int(*s)]).cmp(o),
-            (s @ List(_), Int(o)) => s.cmp(&List(vec![Int(*o)])),
+            (Int(s), o @ List(_)) => List(vec![Int(s.clone())]).cmp(o),
+            (s @ List(_), Int(o)) => s.cmp(&List(vec![Int(o.clone())])),
         }
     }
 }
 
-impl PartialOrd for IntOrList {
+impl<N: Ord + Clone> PartialOrd for IntOrList<N> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Default for IntOrList {
+impl<N: Default> Default for IntOrList<N> {
     fn default() -> Self {
-        Int(0)
+        Int(N::default())
     }
 }
 
-impl Display for IntOrList {
+impl<N: Display> Display for IntOrList<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Int(a) => a.fmt(f),
             List(ls) => {
-                write!(f, "{}", "[")?;
+                write!(f, "[")?;
                 for (idx, i) in ls.iter().enumerate() {
-                    i.fmt(f)?;
-                    if idx < ls.len() - 1 {
-                        write!(f, "{}", ", ")?;
+                    if idx > 0 {
+                        write!(f, ",")?;
                     }
+                    i.fmt(f)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PacketErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    InvalidInt,
+    TrailingInput,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PacketError {
+    pub position: usize,
+    pub kind: PacketErrorKind,
+}
+
+impl Display for PacketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            PacketErrorKind::UnexpectedEnd => write!(f, "unexpected end of packet")?,
+            PacketErrorKind::UnexpectedChar(c) => write!(f, "unexpected {:?}", c)?,
+            PacketErrorKind::InvalidInt => write!(f, "integer does not fit")?,
+            PacketErrorKind::TrailingInput => write!(f, "trailing input after packet")?,
+        }
+        write!(f, " at byte {}", self.position)
+    }
+}
+
+impl Error for PacketError {}
+
+/// Recursive-descent parser for the packet grammar. Integers are sliced straight out of the
+/// input and handed to `N::from_str`, so the integer width is whatever `N` is.
+pub struct PacketParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> PacketParser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        PacketParser { input, pos: 0 }
+    }
+
+    /// Parse one packet, which must make up the whole input.
+    pub fn parse<N: FromStr>(mut self) -> Result<IntOrList<N>, PacketError> {
+        let packet = self.parse_value()?;
+        if self.pos < self.input.len() {
+            return Err(self.error(PacketErrorKind::TrailingInput));
+        }
+        Ok(packet)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, kind: PacketErrorKind) -> PacketError {
+        PacketError {
+            position: self.pos,
+            kind,
+        }
+    }
+
+    fn unexpected(&self) -> PacketError {
+        match self.input[self.pos..].chars().next() {
+            Some(c) => self.error(PacketErrorKind::UnexpectedChar(c)),
+            None => self.error(PacketErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn parse_value<N: FromStr>(&mut self) -> Result<IntOrList<N>, PacketError> {
+        match self.peek() {
+            Some(b'[') => self.parse_list(),
+            Some(b'-' | b'0'..=b'9') => self.parse_int(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_list<N: FromStr>(&mut self) -> Result<IntOrList<N>, PacketError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(List(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(List(items));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn parse_int<N: FromStr>(&mut self) -> Result<IntOrList<N>, PacketError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let digits_start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == digits_start {
+            return Err(self.unexpected());
+        }
+        self.input[start..self.pos]
+            .parse()
+            .map(Int)
+            .map_err(|_| PacketError {
+                position: start,
+                kind: PacketErrorKind::InvalidInt,
+            })
+    }
+}
+
+pub fn parse_packet<N: FromStr>(input: &str) -> Result<IntOrList<N>, PacketError> {
+    PacketParser::new(input).parse()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// Which rule decided a comparison, along with the elements it looked at.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CmpRule<'a, N> {
+    /// Two integers differed.
+    IntCompare { left: &'a N, right: &'a N },
+    /// The list on `side` ran out of items first, while the other side still had `other`.
+    ListRanOut { side: Side, other: &'a IntOrList<N> },
+    /// The integer on `side` was compared against a list, so it was converted to a list
+    /// holding just itself, and `then` decided the comparison from there.
+    Promoted {
+        side: Side,
+        then: Box<CmpRule<'a, N>>,
+    },
+    /// Nothing decided; the packets compare equal.
+    Exhausted,
+}
+
+impl<'a, N> CmpRule<'a, N> {
+    /// The rule that actually decided, under any promotions.
+    pub fn deciding(&self) -> &CmpRule<'a, N> {
+        match self {
+            CmpRule::Promoted { then, .. } => then.deciding(),
+            rule => rule,
+        }
+    }
+
+    /// Sides whose integers were promoted on the way down, outermost first.
+    pub fn promotions(&self) -> Vec<Side> {
+        let mut sides = vec![];
+        let mut rule = self;
+        while let CmpRule::Promoted { side, then } = rule {
+            sides.push(*side);
+            rule = then;
+        }
+        sides
+    }
+}
+
+/// Why two packets compare the way they do. Paths index into each packet from the root; an
+/// integer promoted to a list adds no index on its side.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CmpExplanation<'a, N> {
+    pub ordering: Ordering,
+    pub rule: CmpRule<'a, N>,
+    pub left_path: Vec<usize>,
+    pub right_path: Vec<usize>,
+}
+
+fn fmt_path(f: &mut std::fmt::Formatter<'_>, side: Side, path: &[usize]) -> std::fmt::Result {
+    write!(f, "{}", side)?;
+    for idx in path {
+        write!(f, "[{}]", idx)?;
+    }
+    Ok(())
+}
+
+impl<N: Display> Display for CmpExplanation<'_, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.rule.deciding() {
+            CmpRule::Exhausted => return write!(f, "packets are equal, nothing decided"),
+            CmpRule::IntCompare { left, right } => {
+                fmt_path(f, Side::Left, &self.left_path)?;
+                write!(f, " = {} vs ", left)?;
+                fmt_path(f, Side::Right, &self.right_path)?;
+                write!(f, " = {}: ", right)?;
+                match self.ordering {
+                    Ordering::Less => write!(f, "left integer is lower")?,
+                    _ => write!(f, "left integer is higher")?,
                 }
-                write!(f, "{}", "]")?;
-                Ok(())
             }
+            CmpRule::ListRanOut { side, other } => {
+                let (path, other_side, other_path) = match side {
+                    Side::Left => (&self.left_path, Side::Right, &self.right_path),
+                    Side::Right => (&self.right_path, Side::Left, &self.left_path),
+                };
+                fmt_path(f, *side, path)?;
+                write!(f, " ran out of items before ")?;
+                fmt_path(f, other_side, other_path)?;
+                write!(f, " = {}", other)?;
+            }
+            CmpRule::Promoted { .. } => unreachable!("deciding() looks through promotions"),
+        }
+        for side in self.rule.promotions() {
+            write!(f, ", after converting the {} integer to a list", side)?;
+        }
+        match self.ordering {
+            Ordering::Less => write!(f, ", so the pair is in the right order"),
+            _ => write!(f, ", so the pair is not in the right order"),
         }
     }
 }
 
+#[derive(Default)]
+struct Explainer {
+    left_path: Vec<usize>,
+    right_path: Vec<usize>,
+}
+
+impl Explainer {
+    fn decide<'a, N>(&self, ordering: Ordering, rule: CmpRule<'a, N>) -> CmpExplanation<'a, N> {
+        CmpExplanation {
+            ordering,
+            rule,
+            left_path: self.left_path.clone(),
+            right_path: self.right_path.clone(),
+        }
+    }
+
+    fn values<'a, N: Ord>(
+        &mut self,
+        l: &'a IntOrList<N>,
+        r: &'a IntOrList<N>,
+    ) -> Option<CmpExplanation<'a, N>> {
+        let promoted = |side, mut exp: CmpExplanation<'a, N>| {
+            exp.rule = CmpRule::Promoted {
+                side,
+                then: Box::new(exp.rule),
+            };
+            exp
+        };
+        match (l, r) {
+            (Int(a), Int(b)) => match a.cmp(b) {
+                Ordering::Equal => None,
+                ord => Some(self.decide(ord, CmpRule::IntCompare { left: a, right: b })),
+            },
+            (List(a), List(b)) => self.lists(a, true, b, true),
+            (Int(_), List(b)) => self
+                .lists(std::slice::from_ref(l), false, b, true)
+                .map(|exp| promoted(Side::Left, exp)),
+            (List(a), Int(_)) => self
+                .lists(a, true, std::slice::from_ref(r), false)
+                .map(|exp| promoted(Side::Right, exp)),
+        }
+    }
+
+    // A promoted integer is a list that only exists for the comparison, so it adds nothing to
+    // the path on its side.
+    fn lists<'a, N: Ord>(
+        &mut self,
+        a: &'a [IntOrList<N>],
+        a_real: bool,
+        b: &'a [IntOrList<N>],
+        b_real: bool,
+    ) -> Option<CmpExplanation<'a, N>> {
+        for idx in 0..max(a.len(), b.len()) {
+            if a_real && idx < a.len() {
+                self.left_path.push(idx);
+            }
+            if b_real && idx < b.len() {
+                self.right_path.push(idx);
+            }
+            let res = match (a.get(idx), b.get(idx)) {
+                (Some(x), Some(y)) => self.values(x, y),
+                (None, Some(y)) => {
+                    let rule = CmpRule::ListRanOut {
+                        side: Side::Left,
+                        other: y,
+                    };
+                    Some(self.decide(Ordering::Less, rule))
+                }
+                (Some(x), None) => {
+                    let rule = CmpRule::ListRanOut {
+                        side: Side::Right,
+                        other: x,
+                    };
+                    Some(self.decide(Ordering::Greater, rule))
+                }
+                (None, None) => unreachable!("index past the end of both lists"),
+            };
+            if a_real && idx < a.len() {
+                self.left_path.pop();
+            }
+            if b_real && idx < b.len() {
+                self.right_path.pop();
+            }
+            if res.is_some() {
+                return res;
+            }
+        }
+        None
+    }
+}
+
+impl<N: Ord> IntOrList<N> {
+    /// Compare like `Ord`, but report where and by which rule the comparison was decided.
+    pub fn explain_cmp<'a>(&'a self, other: &'a Self) -> CmpExplanation<'a, N> {
+        let mut explainer = Explainer::default();
+        explainer
+            .values(self, other)
+            .unwrap_or_else(|| explainer.decide(Ordering::Equal, CmpRule::Exhausted))
+    }
+}
+
 #[aoc(2022, day13)]
 pub mod solutions {
     use super::*;
 
-    pub type GenData = Vec<(IntOrList, IntOrList)>;
+    pub type Packet = IntOrList<u64>;
+    pub type GenData = Vec<(Packet, Packet)>;
+    pub type ExplainedData = WithParams<GenData, Params>;
     pub type OutData = usize;
 
+    fn packet_or_panic(s: &str) -> Packet {
+        let s = s.trim();
+        parse_packet(s).unwrap_or_else(|e| panic!("Invalid packet {:?}: {}", s, e))
+    }
+
     #[generator(mapmap)]
     pub fn input_generator(input: &str) -> GenData {
         let input = input.trim_start();
         let pair_list = input.split("\n\n");
         pair_list
             .map(|s| s.split_once("\n").unwrap())
-            .map(|(a, b)| (from_str(a).unwrap(), from_str(b).unwrap()))
+            .map(|(a, b)| (packet_or_panic(a), packet_or_panic(b)))
             .collect_vec()
     }
 
@@ -136,18 +478,46 @@ pub mod solutions {
             .sum()
     }
 
+    pub fn explain_pairs(input: &GenData) -> impl Iterator<Item = String> + '_ {
+        input
+            .iter()
+            .enumerate()
+            .map(|(idx, (a, b))| format!("Pair {}: {}", idx + 1, a.explain_cmp(b)))
+    }
+
+    #[generator(explain)]
+    pub fn input_generator_explained(input: &str) -> ExplainedData {
+        params::parse_with(input, input_generator)
+    }
+
+    /// Part 1 again, printing why each pair is or isn't in order to stderr when the `explain`
+    /// parameter is set.
+    #[solver(part1, explained)]
+    pub fn solve_part1_explained(input: ExplainedData) -> OutData {
+        if input.params.explain {
+            explain_pairs(&input.data).for_each(|ln| eprintln!("{}", ln));
+        }
+        input
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(_, (a, b))| a.explain_cmp(b).ordering != Ordering::Greater)
+            .map(|(idx, _)| idx + 1)
+            .sum()
+    }
+
     #[generator(filtermap)]
-    pub fn input_generator_p2(input: &str) -> Vec<IntOrList> {
+    pub fn input_generator_p2(input: &str) -> Vec<Packet> {
         let input = input.to_owned() + "\n[[2]]\n[[6]]\n";
         input
             .lines()
             .filter(|s| s.trim() != "")
-            .map(|s| from_str(s).unwrap())
+            .map(packet_or_panic)
             .collect_vec()
     }
 
     #[solver(part2, sortyhack)]
-    pub fn solve_part2(input: Vec<IntOrList>) -> OutData {
+    pub fn solve_part2(input: Vec<Packet>) -> OutData {
         let mut input = input.to_vec();
         input.sort();
         let mut two_idx: usize = input.len() + 3;
@@ -171,8 +541,9 @@ pub mod solutions {
 
 #[cfg(test)]
 pub mod test {
+    use super::*;
     use aoc_zen_runner_macros::aoc_case;
-    
+
     #[aoc_case(13, 140)]
     const test_in: &str = r#"
 [1,1,3,1,1]
@@ -200,4 +571,112 @@ pub mod test {
 [1,[2,[3,[4,[5,6,0]]]],8,9]
 "#;
 
+    #[test]
+    fn test_round_trip() {
+        for ln in test_in.lines().filter(|ln| !ln.is_empty()) {
+            let packet: IntOrList = parse_packet(ln).unwrap();
+            let printed = packet.to_string();
+            assert_eq!(printed, ln);
+            assert_eq!(parse_packet(&printed), Ok(packet));
+        }
+    }
+
+    #[test]
+    fn test_explain_cmp() {
+        let pairs = solutions::input_generator(test_in);
+        for (a, b) in pairs.iter() {
+            assert_eq!(a.explain_cmp(b).ordering, a.cmp(b));
+        }
+
+        let exp = pairs[2].0.explain_cmp(&pairs[2].1);
+        assert_eq!(
+            exp.rule,
+            CmpRule::Promoted {
+                side: Side::Left,
+                then: Box::new(CmpRule::IntCompare {
+                    left: &9,
+                    right: &8
+                })
+            }
+        );
+        assert_eq!(exp.left_path, vec![0]);
+        assert_eq!(exp.right_path, vec![0, 0]);
+        assert_eq!(exp.rule.promotions(), vec![Side::Left]);
+        assert_eq!(
+            exp.to_string(),
+            "left[0] = 9 vs right[0][0] = 8: left integer is higher, \
+             after converting the left integer to a list, so the pair is not in the right order"
+        );
+
+        let exp = pairs[3].0.explain_cmp(&pairs[3].1);
+        assert_eq!(
+            exp.rule,
+            CmpRule::ListRanOut {
+                side: Side::Left,
+                other: &Int(4)
+            }
+        );
+        assert_eq!(exp.left_path, Vec::<usize>::new());
+        assert_eq!(exp.right_path, vec![3]);
+
+        // [[]] against [1]: the 1 is promoted, then the left list runs out inside it.
+        let (a, b) = (parse_packet("[[]]").unwrap(), parse_packet("[1]").unwrap());
+        let exp = a.explain_cmp(&b);
+        assert_eq!(
+            exp.rule.deciding(),
+            &CmpRule::ListRanOut {
+                side: Side::Left,
+                other: &Int(1)
+            }
+        );
+        assert_eq!(exp.rule.promotions(), vec![Side::Right]);
+        assert_eq!(
+            exp.to_string(),
+            "left[0] ran out of items before right[0] = 1, \
+             after converting the right integer to a list, so the pair is in the right order"
+        );
+
+        let exp = pairs[0].0.explain_cmp(&pairs[0].0);
+        assert_eq!(exp.rule, CmpRule::Exhausted);
+        assert_eq!(exp.ordering, Ordering::Equal);
+
+        let explained = aoc_common::params::with_params(Params { explain: true }, || {
+            solutions::solve_part1_explained(solutions::input_generator_explained(test_in))
+        });
+        assert_eq!(explained, 13);
+    }
+
+    #[test]
+    fn test_int_width() {
+        assert_eq!(parse_packet::<u64>("[300]"), Ok(List(vec![Int(300)])));
+        assert_eq!(
+            parse_packet::<i8>("[1,300]"),
+            Err(PacketError {
+                position: 3,
+                kind: PacketErrorKind::InvalidInt
+            })
+        );
+        assert_eq!(parse_packet::<i8>("[-5]"), Ok(List(vec![Int(-5)])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |position, kind| Err(PacketError { position, kind });
+        assert_eq!(
+            parse_packet::<u64>("[1,,2]"),
+            err(3, PacketErrorKind::UnexpectedChar(','))
+        );
+        assert_eq!(
+            parse_packet::<u64>("[[1]"),
+            err(4, PacketErrorKind::UnexpectedEnd)
+        );
+        assert_eq!(
+            parse_packet::<u64>("[1]]"),
+            err(3, PacketErrorKind::TrailingInput)
+        );
+        assert_eq!(
+            parse_packet::<u64>("[1 ]"),
+            err(2, PacketErrorKind::UnexpectedChar(' '))
+        );
+    }
 }
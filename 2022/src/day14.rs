@@ -1,6 +1,6 @@
 use aoc_zen_runner_macros::{solver, generator, aoc};
 use grid::Grid;
-use itertools::{Itertools, MinMaxResult};
+use itertools::Itertools;
 use std::cmp::max;
 use std::{
     cmp::min,
@@ -29,307 +29,562 @@ impl Display for Cell {
 
 use Cell::*;
 
-pub struct GenData {
-    x_offset: usize,
-    y_abyss: usize,
-    grid: Grid<Cell>,
-}
+pub type Point = (i64, i64);
 
-#[aoc(2022, day14)]
-pub mod solutions {
-    use super::*;
+/// Where the puzzle's sand comes from.
+pub const SOURCE: Point = (500, 0);
 
-    pub type OutData = u64;
+/// What a grain of sand does next from where it is.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Fall {
+    Moved(Point),
+    Rest,
+    Abyss,
+}
 
-    pub fn parser(input: &str, gen_floor: bool) -> GenData {
-        let input = input.trim_start();
-        let paths: Vec<Vec<(usize, usize)>> = input
-            .lines()
-            .map(|path| {
-                path.split(" -> ")
-                    .map(|s| {
-                        let Some((x, y)) = s.split_once(',') else {
-                            panic!("Invalid coordinate: {}", s)
-                        };
-                        let x: usize = x.parse().unwrap();
-                        let y: usize = y.parse().unwrap();
-                        (x, y)
-                    })
-                    .collect_vec()
-            })
-            .collect_vec();
+/// Everything that happens while sand pours into a cave, in order.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SandEvent {
+    /// A new grain appeared at a source.
+    Spawned(Point),
+    Moved {
+        from: Point,
+        to: Point,
+    },
+    /// The current grain came to rest here.
+    Rest(Point),
+    /// The current grain fell past the lowest wall from here. Nothing more happens after this.
+    Abyss(Point),
+    /// A source is filled and won't spawn any more grains.
+    SourceBlocked(Point),
+}
 
-        let MinMaxResult::MinMax(min_x, max_x) = paths
-            .iter()
-            .flat_map(|ps| ps.iter().map(|pt| pt.0))
-            .minmax()
-        else {
-            panic!("MinMaxResult X produced fewer than 2 distinct values.");
-        };
-        let MinMaxResult::MinMax(_, max_y) = paths
-            .iter()
-            .flat_map(|ps| ps.iter().map(|pt| pt.1))
-            .minmax()
-        else {
-            panic!("MinMaxResult Y produced fewer than 2 distinct values.");
-        };
+/// The scanned cave. The grid only covers cells sand can actually come to rest in, worked
+/// out from the walls, the sources and (with a floor) the triangle below each source;
+/// anything outside it is open air.
+#[derive(Debug, Clone)]
+pub struct Cave {
+    grid: Grid<Cell>,
+    x_min: i64,
+    y_min: i64,
+    /// The lowest wall or source. Without a floor, sand below this falls forever.
+    y_max: i64,
+    floor: Option<i64>,
+    sources: Vec<Point>,
+}
 
-        // println!("X Range: {}-{}", min_x, max_x);
-        // println!("Y Range: {}-{}", min_y, max_y);
+impl Cave {
+    /// A cave with sand coming from the puzzle's one source.
+    pub fn new(paths: &[Vec<Point>], with_floor: bool) -> Cave {
+        Cave::with_sources(paths, &[SOURCE], with_floor)
+    }
 
-        let x_offset = min_x - 200;
-        let y_abyss = max_y + 4;
+    /// A cave with sand coming from each of `sources` in turn, one grain at a time.
+    pub fn with_sources(paths: &[Vec<Point>], sources: &[Point], with_floor: bool) -> Cave {
+        assert!(!sources.is_empty(), "Need at least one source of sand");
+        let points = paths.iter().flatten().chain(sources);
+        let (x_min, x_max) = points.clone().fold((i64::MAX, i64::MIN), |(lo, hi), p| {
+            (min(lo, p.0), max(hi, p.0))
+        });
+        let (y_min, y_max) = points.fold((i64::MAX, i64::MIN), |(lo, hi), p| {
+            (min(lo, p.1), max(hi, p.1))
+        });
 
-        let grid: Grid<Cell> = Grid::new(max_y + 6, max_x - x_offset + 200);
+        let floor = with_floor.then_some(y_max + 2);
+        // Sand spreads at most one column per row it falls, so with a floor it stays within
+        // the triangles below the sources; without one it can only rest on something next to
+        // a wall.
+        let (x_min, x_max) = match floor {
+            Some(f) => sources.iter().fold((x_min, x_max), |(lo, hi), s| {
+                let reach = f - s.1;
+                (min(lo, s.0 - reach), max(hi, s.0 + reach))
+            }),
+            None => (x_min - 1, x_max + 1),
+        };
 
-        let mut res = GenData {
-            grid,
-            x_offset,
-            y_abyss,
+        let rows = (floor.unwrap_or(y_max + 1) - y_min) as usize;
+        let cols = (x_max - x_min + 1) as usize;
+        let mut cave = Cave {
+            grid: Grid::new(rows, cols),
+            x_min,
+            y_min,
+            y_max,
+            floor,
+            sources: sources.to_vec(),
         };
 
         for path in paths {
+            if let [p] = path[..] {
+                cave.set(p, Stone);
+            }
             for seg in path.windows(2) {
                 let [p1, p2] = seg else {
                     panic!("Logical contradiction in window extraction: {:?}", seg)
                 };
-                if p1.0 == p2.0 {
-                    let x = p1.0 - x_offset;
-                    let start = min(p1.1, p2.1);
-                    let stop = max(p1.1, p2.1);
-                    for y in start..=stop {
-                        let cell = res.grid.get_mut(y, x).unwrap();
-                        *cell = Stone;
-                    }
-                } else {
-                    assert_eq!(
-                        p1.1, p2.1,
-                        "Neither X nor Y were equal in segment: {:?}",
-                        seg
-                    );
-                    let y = p1.1;
-                    let start = min(p1.0, p2.0);
-                    let stop = max(p1.0, p2.0);
-                    for x in start..=stop {
-                        let x = x - x_offset;
-                        let cell = res.grid.get_mut(y, x).unwrap();
-                        *cell = Stone;
+                assert!(
+                    p1.0 == p2.0 || p1.1 == p2.1,
+                    "Neither X nor Y were equal in segment: {:?}",
+                    seg
+                );
+                for x in min(p1.0, p2.0)..=max(p1.0, p2.0) {
+                    for y in min(p1.1, p2.1)..=max(p1.1, p2.1) {
+                        cave.set((x, y), Stone);
                     }
                 }
             }
         }
 
-        if gen_floor {
-            for c in res.grid.iter_row_mut(max_y + 2) {
-                *c = Stone;
-            }
-        }
-
-        //draw_cave(&res, "Initial Grid:");
+        cave
+    }
 
-        res
+    pub fn sources(&self) -> &[Point] {
+        &self.sources
     }
 
-    #[generator(part1)]
-    pub fn input_p1(input: &str) -> GenData {
-        parser(input, false)
+    fn index(&self, (x, y): Point) -> Option<(usize, usize)> {
+        let row = usize::try_from(y - self.y_min).ok()?;
+        let col = usize::try_from(x - self.x_min).ok()?;
+        (row < self.grid.rows() && col < self.grid.cols()).then_some((row, col))
     }
 
-    #[generator(part2)]
-    pub fn input_p2(input: &str) -> GenData {
-        parser(input, true)
+    pub fn get(&self, p: Point) -> Cell {
+        if Some(p.1) == self.floor {
+            return Stone;
+        }
+        match self.index(p) {
+            Some((row, col)) => self.grid[(row, col)],
+            None => Nothing,
+        }
     }
 
-    pub fn get_next_locs(data: &GenData, (x, y): (usize, usize)) -> Option<[Cell; 3]> {
-        let GenData {
-            grid,
-            x_offset,
-            y_abyss,
-        } = data;
+    pub fn set(&mut self, p: Point, cell: Cell) {
+        let (row, col) = self
+            .index(p)
+            .unwrap_or_else(|| panic!("Location {:?} is outside the cave", p));
+        self.grid[(row, col)] = cell;
+    }
 
-        if y + 1 == *y_abyss {
-            return None;
+    pub fn step(&self, (x, y): Point) -> Fall {
+        if self.floor.is_none() && y >= self.y_max {
+            return Fall::Abyss;
         }
-
-        let (x, false) = x.overflowing_sub(*x_offset) else {
-            panic!(
-                "Could not offset X-Coord of point {:?} by {}",
-                (x, y),
-                x_offset
-            )
-        };
-        let c1 = *grid.get(y + 1, x).unwrap_or_else(|| {
-            panic!(
-                "Coords could not be offset: {:?} {}",
-                (x + x_offset, y),
-                "c1"
-            )
-        });
-        let c2 = *grid.get(y + 1, x - 1).unwrap_or_else(|| {
-            panic!(
-                "Coords could not be offset: {:?} {}",
-                (x + x_offset, y),
-                "c2"
-            )
-        });
-        let c3 = *grid.get(y + 1, x + 1).unwrap_or_else(|| {
-            panic!(
-                "Coords could not be offset: {:?} {}",
-                (x + x_offset, y),
-                "c3"
-            )
-        });
-        Some([c1, c2, c3])
+        [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+            .into_iter()
+            .find(|&p| self.get(p) == Nothing)
+            .map_or(Fall::Rest, Fall::Moved)
     }
 
-    #[allow(unused)]
-    pub fn draw_cave(cave: &GenData, msg: &str) {
-        let GenData {
-            grid,
-            x_offset,
-            y_abyss: _,
-        } = cave;
+    /// Pour sand grain by grain, taking turns between the sources that aren't blocked, until
+    /// a grain falls into the abyss or every source is blocked. Each grain falls all the way
+    /// from its source.
+    pub fn events(&mut self) -> SandEvents<'_> {
+        SandEvents {
+            active: self.sources.clone(),
+            cave: self,
+            grain: None,
+            turn: 0,
+            done: false,
+        }
+    }
 
-        let mut s = stdout();
-        println!("{}{}", cursor::Save, msg);
-        for r_idx in 0..grid.rows() {
-            for (c_idx, c) in grid.iter_row(r_idx).enumerate() {
-                if r_idx == 0 && c_idx + x_offset == 500 {
-                    print!("+");
-                } else {
-                    print!("{}", c);
+    /// How many grains come to rest before one falls into the abyss or, with a floor, every
+    /// source is blocked.
+    pub fn rested_count(&mut self) -> u64 {
+        let with_floor = self.floor.is_some();
+        self.events()
+            .filter(|e| match e {
+                SandEvent::Rest(_) => true,
+                SandEvent::Abyss(_) => {
+                    assert!(!with_floor, "Sand fell through the floor");
+                    false
                 }
-            }
-            println!("")
-        }
-        print!("{}", cursor::Restore);
-        s.flush().unwrap();
+                _ => false,
+            })
+            .count() as u64
     }
 
-    #[solver(part1, patterns)]
-    pub fn solve_part1(input: &GenData) -> OutData {
-        let mut input = GenData {
-            x_offset: input.x_offset,
-            grid: input.grid.clone(),
-            y_abyss: input.y_abyss,
-        };
+    /// The same as `rested_count`, but every grain follows the last grain from
+    /// its source until the cell that grain filled, so each source keeps that path as a stack
+    /// and the next grain starts from the position just above the last resting place instead
+    /// of from the source. Cells other sources have filled since are popped off first; a cell
+    /// can only fill once everything below it on the path has.
+    pub fn pour(&mut self) -> u64 {
         let mut sand_counter = 0;
-        while input.grid.get(0, 500 - input.x_offset) != Some(&Sand) {
-            sand_counter += 1;
-            let mut current_loc = (500, 0);
-            let Some(mut next_locs) = get_next_locs(&input, current_loc) else {
-                return sand_counter - 1;
+        let mut paths = self
+            .sources
+            .iter()
+            .map(|&source| vec![source])
+            .collect_vec();
+        let mut turn = 0;
+        while !paths.is_empty() {
+            turn %= paths.len();
+            let path = &mut paths[turn];
+            while path.last().is_some_and(|&p| self.get(p) != Nothing) {
+                path.pop();
+            }
+            let Some(&start) = path.last() else {
+                paths.remove(turn);
+                continue;
             };
-            while next_locs.iter().any(|c| *c == Nothing) {
-                match next_locs {
-                    [Nothing, _, _] => {
-                        current_loc.1 += 1;
-                    }
-                    [_, Nothing, _] => {
-                        current_loc.1 += 1;
-                        current_loc.0 -= 1;
-                    }
-                    [_, _, Nothing] => {
-                        current_loc.1 += 1;
-                        current_loc.0 += 1;
-                    }
-                    _ => {
-                        panic!("Logical contradiction while selecting next location")
-                    }
-                }
-
-                //draw_cave(&input, &format!("Sand Unit {}", sand_counter));
-                match get_next_locs(&input, current_loc) {
-                    Some(nl) => {
-                        next_locs = nl;
-                    }
-                    None => {
-                        return sand_counter - 1;
+            turn += 1;
+
+            let mut current_loc = start;
+            loop {
+                match self.step(current_loc) {
+                    Fall::Moved(next) => {
+                        path.push(next);
+                        current_loc = next;
                     }
+                    Fall::Rest => break,
+                    Fall::Abyss => return sand_counter,
                 }
             }
-
-            let c = input
-                .grid
-                .get_mut(current_loc.1, current_loc.0 - input.x_offset)
-                .expect("Final location for grain outside of grid.");
-            assert_eq!(
-                *c, Nothing,
-                "Expected location {:?} to be empty, but found {:?}",
-                current_loc, c
-            );
-            *c = Sand;
+            path.pop();
+            self.set(current_loc, Sand);
+            sand_counter += 1;
         }
+        sand_counter
+    }
 
-        todo!()
+    /// Count the grains that fill a cave with a floor without simulating them. With a floor,
+    /// sand ends up in exactly the cells it can reach from some source, and a cell is
+    /// reachable when it's open and is a source or one of the three cells above it is
+    /// reachable, so this works down the triangles below the sources a row at a time.
+    pub fn triangle_fill(&self) -> u64 {
+        let floor = self
+            .floor
+            .expect("Only a cave with a floor fills up completely");
+        let left = self
+            .sources
+            .iter()
+            .map(|s| s.0 - (floor - s.1))
+            .min()
+            .unwrap();
+        let right = self
+            .sources
+            .iter()
+            .map(|s| s.0 + (floor - s.1))
+            .max()
+            .unwrap();
+        let top = self.sources.iter().map(|s| s.1).min().unwrap();
+
+        let mut reachable = vec![false; (right - left + 1) as usize];
+        let mut count = 0;
+        for y in top..floor {
+            let above = reachable.clone();
+            for (i, cell) in reachable.iter_mut().enumerate() {
+                let x = left + i as i64;
+                let from_above = above[i.saturating_sub(1)..(i + 2).min(above.len())]
+                    .iter()
+                    .any(|&r| r);
+                *cell =
+                    (from_above || self.sources.contains(&(x, y))) && self.get((x, y)) == Nothing;
+                count += *cell as u64;
+            }
+        }
+        count
     }
+}
 
-    #[solver(part2, noabyss)]
-    pub fn solve_part2(input: GenData) -> OutData {
-        let mut input = GenData {
-            x_offset: input.x_offset,
-            grid: input.grid.clone(),
-            y_abyss: input.y_abyss,
+/// The iterator from `Cave::events`. Sand is only placed in the cave as the events are
+/// consumed.
+pub struct SandEvents<'a> {
+    cave: &'a mut Cave,
+    /// Where the grain that's falling now is, if there is one.
+    grain: Option<Point>,
+    /// Sources that aren't blocked yet, in the order they take turns.
+    active: Vec<Point>,
+    turn: usize,
+    done: bool,
+}
+
+impl Iterator for SandEvents<'_> {
+    type Item = SandEvent;
+
+    fn next(&mut self) -> Option<SandEvent> {
+        if self.done {
+            return None;
+        }
+        let Some(current_loc) = self.grain else {
+            if self.active.is_empty() {
+                self.done = true;
+                return None;
+            }
+            self.turn %= self.active.len();
+            let source = self.active[self.turn];
+            if self.cave.get(source) != Nothing {
+                self.active.remove(self.turn);
+                return Some(SandEvent::SourceBlocked(source));
+            }
+            self.turn += 1;
+            self.grain = Some(source);
+            return Some(SandEvent::Spawned(source));
         };
-        let mut sand_counter = 0;
-        while input.grid.get(0, 500 - input.x_offset) != Some(&Sand) {
-            sand_counter += 1;
-            let mut current_loc = (500, 0);
-            let Some(mut next_locs) = get_next_locs(&input, current_loc) else {
-                panic!("Should not have found the abyss, but did.");
-            };
-            while next_locs.iter().any(|c| *c == Nothing) {
-                match next_locs {
-                    [Nothing, _, _] => {
-                        current_loc.1 += 1;
-                    }
-                    [_, Nothing, _] => {
-                        current_loc.1 += 1;
-                        current_loc.0 -= 1;
-                    }
-                    [_, _, Nothing] => {
-                        current_loc.1 += 1;
-                        current_loc.0 += 1;
-                    }
-                    _ => {
-                        panic!("Logical contradiction while selecting next location")
-                    }
-                }
 
-                //draw_cave(&input, &format!("Sand Unit {}", sand_counter));
-                match get_next_locs(&input, current_loc) {
-                    Some(nl) => {
-                        next_locs = nl;
-                    }
-                    None => {
-                        return sand_counter - 1;
-                    }
+        Some(match self.cave.step(current_loc) {
+            Fall::Moved(next) => {
+                self.grain = Some(next);
+                SandEvent::Moved {
+                    from: current_loc,
+                    to: next,
                 }
             }
+            Fall::Rest => {
+                self.cave.set(current_loc, Sand);
+                self.grain = None;
+                SandEvent::Rest(current_loc)
+            }
+            Fall::Abyss => {
+                self.done = true;
+                SandEvent::Abyss(current_loc)
+            }
+        })
+    }
+}
 
-            let c = input
-                .grid
-                .get_mut(current_loc.1, current_loc.0 - input.x_offset)
-                .expect("Final location for grain outside of grid.");
-            assert_eq!(
-                *c, Nothing,
-                "Expected location {:?} to be empty, but found {:?}",
-                current_loc, c
-            );
-            *c = Sand;
+impl Display for Cave {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in 0..self.grid.rows() {
+            let y = self.y_min + row as i64;
+            for col in 0..self.grid.cols() {
+                let x = self.x_min + col as i64;
+                if self.sources.contains(&(x, y)) && self.get((x, y)) == Nothing {
+                    write!(f, "+")?;
+                } else {
+                    write!(f, "{}", self.get((x, y)))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        if let Some(floor) = self.floor {
+            for col in 0..self.grid.cols() {
+                write!(f, "{}", self.get((self.x_min + col as i64, floor)))?;
+            }
+            writeln!(f)?;
         }
+        Ok(())
+    }
+}
 
-        sand_counter
+#[aoc(2022, day14)]
+pub mod solutions {
+    use super::*;
+
+    pub type OutData = u64;
+    /// The same cave type for both parts, under two names so each part's solvers pair with
+    /// the right generator.
+    pub type AbyssCave = Cave;
+    pub type FloorCave = Cave;
+
+    pub fn parse_paths(input: &str) -> Vec<Vec<Point>> {
+        input
+            .lines()
+            .filter(|ln| !ln.trim().is_empty())
+            .map(|path| {
+                path.split(" -> ")
+                    .map(|s| {
+                        let Some((x, y)) = s.trim().split_once(',') else {
+                            panic!("Invalid coordinate: {}", s)
+                        };
+                        (x.parse().unwrap(), y.parse().unwrap())
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    #[generator(part1)]
+    pub fn input_p1(input: &str) -> AbyssCave {
+        Cave::new(&parse_paths(input), false)
+    }
+
+    #[generator(part2)]
+    pub fn input_p2(input: &str) -> FloorCave {
+        Cave::new(&parse_paths(input), true)
+    }
+
+    #[allow(unused)]
+    pub fn draw_cave(cave: &Cave, msg: &str) {
+        let mut s = stdout();
+        println!("{}{}", cursor::Save, msg);
+        print!("{}", cave);
+        print!("{}", cursor::Restore);
+        s.flush().unwrap();
+    }
+
+    #[solver(part1, patterns)]
+    pub fn solve_part1(mut input: AbyssCave) -> OutData {
+        input.rested_count()
+    }
+
+    #[solver(part1, memo)]
+    pub fn solve_part1_memo(mut input: AbyssCave) -> OutData {
+        input.pour()
+    }
+
+    #[solver(part2, noabyss)]
+    pub fn solve_part2(mut input: FloorCave) -> OutData {
+        input.rested_count()
+    }
+
+    #[solver(part2, triangle)]
+    pub fn solve_part2_triangle(input: FloorCave) -> OutData {
+        input.triangle_fill()
     }
 }
 
 #[cfg(test)]
 pub mod test {
+    use advent_of_code_2022::testutil::lcg;
     use aoc_zen_runner_macros::aoc_case;
 
+    use super::solutions::*;
+    use super::*;
+
     #[aoc_case(24, 93)]
     const test_in: &str = r#"
 498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9
 "#;
+
+    #[test]
+    fn test_bounds() {
+        let cave = input_p1(test_in);
+        assert_eq!(
+            cave.to_string(),
+            "\
+.......+....
+............
+............
+............
+.....#...##.
+.....#...#..
+...###...#..
+.........#..
+.........#..
+.#########..
+"
+        );
+
+        // Walls at the left edge, and a source far from any wall.
+        let edge = "0,3 -> 0,5 -> 4,5\n";
+        assert_eq!(solve_part1(input_p1(edge)), 0);
+        let floor = input_p2(edge);
+        assert_eq!(floor.get((500, 7)), Stone);
+        assert_eq!(floor.get((-1000, 7)), Stone);
+        assert_eq!(solve_part2(floor), 49);
+
+        // A single point and a wall straight under the source.
+        assert_eq!(solve_part1(input_p1("500,2\n")), 0);
+        assert_eq!(solve_part2(input_p2("500,2\n")), 15);
+        assert_eq!(solve_part1(input_p1("499,2 -> 501,2\n")), 1);
+    }
+
+    #[test]
+    fn test_engines_agree() {
+        let mut rand = lcg(14);
+        let mut inputs = vec![
+            test_in.to_string(),
+            "0,3 -> 0,5 -> 4,5\n".to_string(),
+            // A bowl that fills up to the source even without a floor.
+            "497,0 -> 497,3 -> 503,3 -> 503,0\n".to_string(),
+        ];
+        for _ in 0..30 {
+            let walls = (0..rand(6) + 1)
+                .map(|_| {
+                    let (x, y) = (490 + rand(20), 1 + rand(12));
+                    let (dx, dy) = if rand(2) == 0 {
+                        (rand(6), 0)
+                    } else {
+                        (0, rand(6))
+                    };
+                    format!("{},{} -> {},{}", x, y, x + dx, y + dy)
+                })
+                .join("\n");
+            inputs.push(walls);
+        }
+
+        for input in &inputs {
+            let mut memo = input_p1(input);
+            assert_eq!(memo.pour(), solve_part1(input_p1(input)), "{}", input);
+            let mut retrace = input_p1(input);
+            retrace.events().for_each(drop);
+            assert_eq!(memo.to_string(), retrace.to_string(), "{}", input);
+
+            let expected = solve_part2(input_p2(input));
+            assert_eq!(input_p2(input).pour(), expected, "{}", input);
+            assert_eq!(input_p2(input).triangle_fill(), expected, "{}", input);
+
+            let paths = parse_paths(input);
+            let sources = (0..rand(3) + 2)
+                .map(|_| (490 + rand(20) as i64, rand(6) as i64))
+                .collect_vec();
+            for with_floor in [false, true] {
+                let mut memo = Cave::with_sources(&paths, &sources, with_floor);
+                let mut retrace = memo.clone();
+                let rested = retrace.rested_count();
+                if with_floor {
+                    assert_eq!(memo.triangle_fill(), rested, "{} {:?}", input, sources);
+                }
+                assert_eq!(memo.pour(), rested, "{} {:?}", input, sources);
+                assert_eq!(memo.to_string(), retrace.to_string(), "{:?}", sources);
+            }
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let mut cave = input_p1(test_in);
+        let first_grain = cave
+            .events()
+            .take_while(|e| !matches!(e, SandEvent::Rest(_)))
+            .collect_vec();
+        assert_eq!(first_grain[0], SandEvent::Spawned(SOURCE));
+        assert_eq!(first_grain.len(), 9);
+        assert_eq!(
+            first_grain[8],
+            SandEvent::Moved {
+                from: (500, 7),
+                to: (500, 8)
+            }
+        );
+
+        // How many grains come to rest on each row.
+        let mut cave = input_p1(test_in);
+        let heights = cave
+            .events()
+            .filter_map(|e| match e {
+                SandEvent::Rest((_, y)) => Some(y),
+                _ => None,
+            })
+            .counts();
+        assert_eq!(heights[&8], 6);
+        assert_eq!(heights.values().sum::<usize>(), 24);
+        assert!(matches!(cave.events().next(), Some(SandEvent::Spawned(_))));
+
+        // One source sits right under another, so it's buried first, and then the sand piles
+        // up to the top one.
+        let mut cave = Cave::with_sources(&[], &[SOURCE, (500, 2)], true);
+        let blocked = cave
+            .events()
+            .filter(|e| matches!(e, SandEvent::SourceBlocked(_)))
+            .collect_vec();
+        assert_eq!(
+            blocked,
+            [
+                SandEvent::SourceBlocked((500, 2)),
+                SandEvent::SourceBlocked(SOURCE)
+            ]
+        );
+        // Pouring again starts a new stream, which finds both still blocked.
+        assert_eq!(cave.events().count(), 2);
+
+        // A source inside a wall never spawns anything.
+        let mut walled = Cave::with_sources(&parse_paths("500,0 -> 500,1"), &[SOURCE], true);
+        assert_eq!(
+            walled.events().collect_vec(),
+            [SandEvent::SourceBlocked(SOURCE)]
+        );
+        assert_eq!(walled.triangle_fill(), 0);
+    }
 }
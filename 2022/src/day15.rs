@@ -1,94 +1,155 @@
+use advent_of_code_2022::intervals::IntervalSet;
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use std::cmp::{max, min};
 use std::collections::HashSet;
-use std::mem::take;
-use std::ops::{Add, RangeInclusive};
-
-fn range_overlaps<T>(a: &RangeInclusive<T>, b: &RangeInclusive<T>) -> bool
-where
-    T: Ord,
-{
-    !(a.end() < b.start() || b.end() < a.start())
-}
+use std::ops::RangeInclusive;
 
-fn range_adjacent<T>(a: &RangeInclusive<T>, b: &RangeInclusive<T>) -> bool
-where
-    T: Ord + Add<T> + From<i32> + Copy,
-    <T as std::ops::Add>::Output: PartialEq<T>,
-{
-    *a.end() + 1.into() == *b.start() || *b.end() + 1.into() == *a.start()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sensor {
+    pub x: i64,
+    pub y: i64,
+    pub radius: i64,
 }
 
-#[derive(Debug)]
-pub struct RangeLine {
-    ranges_set: Vec<RangeInclusive<i64>>,
-}
+impl Sensor {
+    pub fn covers(&self, (x, y): (i64, i64)) -> bool {
+        (self.x.abs_diff(x) + self.y.abs_diff(y)) as i64 <= self.radius
+    }
 
-impl RangeLine {
-    fn new() -> RangeLine {
-        RangeLine {
-            ranges_set: Vec::new(),
-        }
+    // In rotated coordinates (u = x + y, v = x - y) the diamond just outside the sensor's
+    // reach is bounded by two lines of constant u and two of constant v.
+    fn outside_u_lines(&self) -> [i64; 2] {
+        let u = self.x + self.y;
+        [u - self.radius - 1, u + self.radius + 1]
     }
 
-    fn set_range(&mut self, x: &RangeInclusive<i64>) {
-        let mut x = x.clone();
-        for idx in 0..self.ranges_set.len() {
-            while idx < self.ranges_set.len()
-                && (range_overlaps(&x, &self.ranges_set[idx])
-                    || range_adjacent(&x, &self.ranges_set[idx]))
-            {
-                let old = self.ranges_set.remove(idx);
-                let existing_min = *old.start();
-                let existing_max = *old.end();
-                let new_min = min(*x.start(), existing_min);
-                let new_max = max(*x.end(), existing_max);
-                x = new_min..=new_max;
-            }
-        }
-        self.ranges_set.push(x);
+    fn outside_v_lines(&self) -> [i64; 2] {
+        let v = self.x - self.y;
+        [v - self.radius - 1, v + self.radius + 1]
     }
+}
+
+pub fn covered(sensors: &[Sensor], pt: (i64, i64)) -> bool {
+    sensors.iter().any(|s| s.covers(pt))
+}
 
-    fn unset_point(&mut self, pt: i64) {
-        for idx in 0..self.ranges_set.len() {
-            if self.ranges_set[idx].contains(&pt) {
-                let range_a = *self.ranges_set[idx].start()..=(pt - 1);
-                let range_b = (pt + 1)..=*(self.ranges_set[idx].end());
-                self.ranges_set[idx] = range_a;
-                self.set_range(&range_b);
-                return;
+/// Points in the region that no sensor covers, found by intersecting the lines just outside
+/// each sensor's diamond. Only isolated points are guaranteed to be found, which is all part 2
+/// needs; use `uncovered_count` to measure bigger gaps.
+pub fn boundary_candidates(
+    sensors: &[Sensor],
+    xs: RangeInclusive<i64>,
+    ys: RangeInclusive<i64>,
+) -> Vec<(i64, i64)> {
+    let mut us: Vec<i64> = sensors.iter().flat_map(|s| s.outside_u_lines()).collect();
+    let mut vs: Vec<i64> = sensors.iter().flat_map(|s| s.outside_v_lines()).collect();
+    us.sort_unstable();
+    us.dedup();
+    vs.sort_unstable();
+    vs.dedup();
+
+    let (x_lo, x_hi) = (*xs.start(), *xs.end());
+    let (y_lo, y_hi) = (*ys.start(), *ys.end());
+
+    let mut candidates = vec![(x_lo, y_lo), (x_lo, y_hi), (x_hi, y_lo), (x_hi, y_hi)];
+    for &u in us.iter() {
+        for &v in vs.iter() {
+            if (u + v) % 2 == 0 {
+                candidates.push(((u + v) / 2, (u - v) / 2));
             }
         }
+        // A point on the region's edge only needs one diagonal to pin it down.
+        candidates.extend([
+            (x_lo, u - x_lo),
+            (x_hi, u - x_hi),
+            (u - y_lo, y_lo),
+            (u - y_hi, y_hi),
+        ]);
     }
-
-    fn len(&self) -> i64 {
-        self.ranges_set
-            .iter()
-            .map(|r| {
-                let e = *r.end();
-                let s = *r.start();
-                (e.abs_diff(s) + 1) as i64
-            })
-            .sum()
+    for &v in vs.iter() {
+        candidates.extend([
+            (x_lo, x_lo - v),
+            (x_hi, x_hi - v),
+            (v + y_lo, y_lo),
+            (v + y_hi, y_hi),
+        ]);
     }
 
-    fn normalize(&mut self) {
-        let ranges = take(&mut self.ranges_set);
-        for r in ranges {
-            self.set_range(&r);
+    candidates.retain(|(x, y)| xs.contains(x) && ys.contains(y) && !covered(sensors, (*x, *y)));
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+}
+
+/// How many points in the region no sensor covers. Each row's coverage is merged into an
+/// `IntervalSet` and the gaps in it are added up, so the cost depends on the number of rows
+/// and sensors, not on how wide the rows are.
+pub fn uncovered_count(
+    sensors: &[Sensor],
+    xs: RangeInclusive<i64>,
+    ys: RangeInclusive<i64>,
+) -> u64 {
+    let mut row: IntervalSet<i64> = IntervalSet::new();
+    let mut count = 0;
+    for y in ys {
+        row.clear();
+        for s in sensors {
+            let reach = s.radius - s.y.abs_diff(y) as i64;
+            if reach >= 0 {
+                row.insert(s.x - reach..=s.x + reach);
+            }
         }
+        count += row
+            .gaps(xs.clone())
+            .map(|gap| (gap.end() - gap.start() + 1) as u64)
+            .sum::<u64>();
     }
+    count
+}
 
-    fn ranges_count(&self) -> usize {
-        self.ranges_set.len()
+/// Every point in the region that no sensor covers. Rectangles whose four corners all sit
+/// inside one sensor's diamond are skipped whole, so this is quick when coverage is nearly
+/// complete, but it lists each uncovered point individually.
+pub fn uncovered_points(
+    sensors: &[Sensor],
+    xs: RangeInclusive<i64>,
+    ys: RangeInclusive<i64>,
+) -> Vec<(i64, i64)> {
+    let mut results = Vec::new();
+    let mut work = vec![(*xs.start(), *xs.end(), *ys.start(), *ys.end())];
+
+    while let Some((x0, x1, y0, y1)) = work.pop() {
+        if x0 > x1 || y0 > y1 {
+            continue;
+        }
+        let corners = [(x0, y0), (x0, y1), (x1, y0), (x1, y1)];
+        if sensors.iter().any(|s| corners.iter().all(|c| s.covers(*c))) {
+            continue;
+        }
+        if x0 == x1 && y0 == y1 {
+            results.push((x0, y0));
+        } else if x1 - x0 >= y1 - y0 {
+            let mid = x0 + (x1 - x0) / 2;
+            work.push((x0, mid, y0, y1));
+            work.push((mid + 1, x1, y0, y1));
+        } else {
+            let mid = y0 + (y1 - y0) / 2;
+            work.push((x0, x1, y0, mid));
+            work.push((x0, x1, mid + 1, y1));
+        }
     }
 
-    fn contains(&self, point: i64) -> bool {
-        self.ranges_set.iter().any(|r| r.contains(&point))
-    }
+    results.sort_unstable();
+    results
+}
 
-    fn raw<'a>(&'a self) -> &'a Vec<RangeInclusive<i64>> {
-        &self.ranges_set
+puzzle_params! {
+    pub struct Params {
+        target_row: i64 = 2_000_000,
+        max_coord: i64 = 4_000_000,
+        tuning_multiplier: i64 = 4_000_000,
     }
 }
 
@@ -97,14 +158,19 @@ impl RangeLine {
 pub mod solutions {
     use super::*;
 
-    pub type GenData = Vec<(i64, i64, i64, i64)>;
+    pub type Reading = (i64, i64, i64, i64);
+    pub type GenData = WithParams<Vec<Reading>, Params>;
     pub type OutData = usize;
 
     #[generator(day15)]
     pub fn input_generator(input: &str) -> GenData {
+        params::parse_with(input, parse_readings)
+    }
+
+    pub fn parse_readings(input: &str) -> Vec<Reading> {
         let input = input.trim_start();
 
-        let mut data: GenData = Vec::new();
+        let mut data: Vec<Reading> = Vec::new();
 
         for ln in input.lines() {
             let (_, rest) = ln.split_once('=').unwrap();
@@ -128,19 +194,18 @@ pub mod solutions {
 
     #[solver(part1, loopyloop)]
     pub fn solve_part1(input: GenData) -> OutData {
-        let mut mapped_spots: RangeLine = RangeLine::new();
+        let mut mapped_spots: IntervalSet<i64> = IntervalSet::new();
         let mut beacon_spots: HashSet<i64> = HashSet::new();
 
-        let target_y = if cfg!(test) { 10i64 } else { 2_000_000i64 };
+        let target_y = input.params.target_row;
 
-        for (sensor_x, sensor_y, beacon_x, beacon_y) in input.iter() {
+        for (sensor_x, sensor_y, beacon_x, beacon_y) in input.data.iter() {
             let distance = (sensor_x.abs_diff(*beacon_x) + sensor_y.abs_diff(*beacon_y)) as i64;
             let dist_remain = distance - sensor_y.abs_diff(target_y) as i64;
             if dist_remain > 0 {
                 let min_x = sensor_x - dist_remain;
                 let max_x = sensor_x + dist_remain;
-                let range = min_x..=max_x;
-                mapped_spots.set_range(&range);
+                mapped_spots.insert(min_x..=max_x);
             }
             if *beacon_y == target_y {
                 beacon_spots.insert(*beacon_x);
@@ -148,73 +213,109 @@ pub mod solutions {
         }
 
         for pt in beacon_spots.iter() {
-            mapped_spots.unset_point(*pt);
+            mapped_spots.remove(*pt..=*pt);
         }
 
-        mapped_spots.normalize();
-
         dbg!(&beacon_spots.len());
         dbg!(&mapped_spots.len());
 
         mapped_spots.len() as usize
     }
 
-    #[cfg(test)]
-    const MAX_COORD: u32 = 20;
-
-    #[cfg(not(test))]
-    const MAX_COORD: u32 = 4_000_000;
-
-    const TUNING_ADJUST: i64 = 4_000_000;
-
     #[solver(part2, distancecheck)]
     pub fn solve_part2(input: GenData) -> OutData {
-        let mut grid: Vec<RangeLine> = Vec::new();
-        for _ in 0..=MAX_COORD {
-            grid.push(RangeLine::new());
+        let max_coord = input.params.max_coord;
+        let mut grid: Vec<IntervalSet<i64>> = Vec::new();
+        for _ in 0..=max_coord {
+            grid.push(IntervalSet::new());
         }
 
-        for (sensor_x, sensor_y, beacon_x, beacon_y) in input.iter() {
+        for (sensor_x, sensor_y, beacon_x, beacon_y) in input.data.iter() {
             let total_distance =
                 (sensor_x.abs_diff(*beacon_x) + sensor_y.abs_diff(*beacon_y)) as i64;
             let min_y = max(sensor_y - total_distance, 0);
-            let max_y = min(sensor_y + total_distance, MAX_COORD as i64);
+            let max_y = min(sensor_y + total_distance, max_coord);
             // For each Y (within bounds) the sensor can cover {
             for y_val in min_y..=max_y {
                 // Do projection to figure out coverage range
                 let distance_remaining = total_distance - y_val.abs_diff(*sensor_y) as i64;
                 let min_x = max(0, *sensor_x - distance_remaining);
-                let max_x = min(MAX_COORD as i64, *sensor_x + distance_remaining);
-                // Set range for that Y's RangeLine
-                let range = min_x..=max_x;
-                grid[y_val as usize].set_range(&range);
+                let max_x = min(max_coord, *sensor_x + distance_remaining);
+                // Set range for that Y's interval set
+                grid[y_val as usize].insert(min_x..=max_x);
             }
         }
 
-        dbg!(grid.iter().map(|r| r.raw().capacity()).max());
+        dbg!(grid.iter().map(|r| r.ranges_count()).max());
 
         for (idx, row) in grid
             .iter()
             .enumerate()
             .filter(|&(_, c)| c.ranges_count() > 1)
         {
-            for x_val in 0..MAX_COORD {
-                if !row.contains(x_val as i64) {
-                    return ((x_val as i64) * TUNING_ADJUST) as usize + idx;
+            for x_val in 0..max_coord {
+                if !row.contains(x_val) {
+                    return (x_val * input.params.tuning_multiplier) as usize + idx;
                 }
             }
         }
 
         0
     }
+
+    pub fn to_sensors(input: &GenData) -> Vec<Sensor> {
+        input
+            .data
+            .iter()
+            .map(|(sensor_x, sensor_y, beacon_x, beacon_y)| Sensor {
+                x: *sensor_x,
+                y: *sensor_y,
+                radius: (sensor_x.abs_diff(*beacon_x) + sensor_y.abs_diff(*beacon_y)) as i64,
+            })
+            .collect()
+    }
+
+    #[solver(part1, region_query)]
+    pub fn solve_part1_region(input: GenData) -> OutData {
+        let sensors = to_sensors(&input);
+        let target_y = input.params.target_row;
+
+        let min_x = sensors.iter().map(|s| s.x - s.radius).min().unwrap_or(0);
+        let max_x = sensors.iter().map(|s| s.x + s.radius).max().unwrap_or(0);
+        let beacons: HashSet<i64> = input
+            .data
+            .iter()
+            .filter(|(_, _, _, beacon_y)| *beacon_y == target_y)
+            .map(|(_, _, beacon_x, _)| *beacon_x)
+            .collect();
+
+        let width = (max_x - min_x + 1) as usize;
+        let uncovered = uncovered_count(&sensors, min_x..=max_x, target_y..=target_y) as usize;
+        width - uncovered - beacons.len()
+    }
+
+    #[solver(part2, boundary_lines)]
+    pub fn solve_part2_boundary(input: GenData) -> OutData {
+        let sensors = to_sensors(&input);
+        let bounds = 0..=input.params.max_coord;
+        let (x, y) = boundary_candidates(&sensors, bounds.clone(), bounds)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| panic!("No uncovered point found in the search area."));
+
+        (x * input.params.tuning_multiplier + y) as usize
+    }
 }
 
 #[cfg(test)]
 pub mod test {
+    use super::solutions::*;
+    use super::*;
     use aoc_zen_runner_macros::aoc_case;
 
     #[aoc_case(26, 56000011)]
     const test_in: &str = r#"
+#! target_row=10 max_coord=20
 Sensor at x=2, y=18: closest beacon is at x=-2, y=15
 Sensor at x=9, y=16: closest beacon is at x=10, y=16
 Sensor at x=13, y=2: closest beacon is at x=15, y=3
@@ -230,4 +331,33 @@ Sensor at x=16, y=7: closest beacon is at x=15, y=3
 Sensor at x=14, y=3: closest beacon is at x=15, y=3
 Sensor at x=20, y=1: closest beacon is at x=15, y=3
 "#;
+
+    #[test]
+    fn test_uncovered_region() {
+        let sensors = to_sensors(&input_generator(test_in));
+
+        assert_eq!(uncovered_points(&sensors, 0..=20, 0..=20), vec![(14, 11)]);
+        assert_eq!(
+            boundary_candidates(&sensors, 0..=20, 0..=20),
+            vec![(14, 11)]
+        );
+
+        let brute: Vec<(i64, i64)> = (-12..=32)
+            .flat_map(|x| (-5..=25).map(move |y| (x, y)))
+            .filter(|pt| !covered(&sensors, *pt))
+            .collect();
+        assert_eq!(uncovered_points(&sensors, -12..=32, -5..=25), brute);
+        assert_eq!(
+            uncovered_count(&sensors, -12..=32, -5..=25),
+            brute.len() as u64
+        );
+        assert_eq!(uncovered_count(&sensors, 0..=20, 0..=20), 1);
+    }
+
+    #[test]
+    fn test_tuning_multiplier() {
+        let input = test_in.replace("max_coord=20", "max_coord=20 tuning_multiplier=100");
+        assert_eq!(solve_part2_boundary(input_generator(&input)), 14 * 100 + 11);
+        assert_eq!(solve_part2(input_generator(&input)), 14 * 100 + 11);
+    }
 }
@@ -1,44 +1,257 @@
-use aoc_zen_runner_macros::{aoc, generator, solution, solver};
+use std::fmt::Display;
+
+use advent_of_code_2022::cycles::find_cycle_hashed_within;
+use aoc_zen_runner_macros::{aoc, generator, solver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jet {
+    Left,
+    Right,
+}
+
+pub const CHAMBER_WIDTH: usize = 7;
+
+/// Rock shapes as they appear, bottom row first. Each row is a bitmask with bit 6 as the
+/// leftmost column, already two columns in from the left wall.
+const ROCKS: [&[u8]; 5] = [
+    &[0b0011110],
+    &[0b0001000, 0b0011100, 0b0001000],
+    &[0b0011100, 0b0000100, 0b0000100],
+    &[0b0010000, 0b0010000, 0b0010000, 0b0010000],
+    &[0b0011000, 0b0011000],
+];
+
+const LEFT_WALL: u8 = 1 << (CHAMBER_WIDTH - 1);
+const RIGHT_WALL: u8 = 1;
+
+/// The tall, narrow chamber, one byte per row from the floor up. Only rows with rock in them
+/// are stored, so the tower height is the number of rows.
+#[derive(Debug, Clone)]
+pub struct Chamber<'a> {
+    rows: Vec<u8>,
+    jets: &'a [Jet],
+    jet_idx: usize,
+    rock_idx: usize,
+    rocks_dropped: u64,
+}
+
+/// Everything that decides how the rest of the simulation goes, up to the shape of the top of
+/// the tower: which rock and jet come next, and how far down each column is open.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChamberState {
+    pub rock_idx: usize,
+    pub jet_idx: usize,
+    pub surface: [usize; CHAMBER_WIDTH],
+}
+
+impl<'a> Chamber<'a> {
+    pub fn new(jets: &'a [Jet]) -> Chamber<'a> {
+        assert!(
+            !jets.is_empty(),
+            "Need at least one jet to push rocks around"
+        );
+        Chamber {
+            rows: vec![],
+            jets,
+            jet_idx: 0,
+            rock_idx: 0,
+            rocks_dropped: 0,
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.rows.len() as u64
+    }
+
+    pub fn rocks_dropped(&self) -> u64 {
+        self.rocks_dropped
+    }
+
+    fn collides(&self, rock: &[u8], bottom: usize) -> bool {
+        rock.iter()
+            .enumerate()
+            .any(|(dy, r)| self.rows.get(bottom + dy).is_some_and(|row| row & r != 0))
+    }
+
+    fn push(&self, rock: &mut [u8], bottom: usize, jet: Jet) {
+        let shifted: Vec<u8> = match jet {
+            Jet::Left if rock.iter().all(|r| r & LEFT_WALL == 0) => {
+                rock.iter().map(|r| r << 1).collect()
+            }
+            Jet::Right if rock.iter().all(|r| r & RIGHT_WALL == 0) => {
+                rock.iter().map(|r| r >> 1).collect()
+            }
+            _ => return,
+        };
+        if !self.collides(&shifted, bottom) {
+            rock.copy_from_slice(&shifted);
+        }
+    }
+
+    /// Drop the next rock until it comes to rest.
+    pub fn drop_rock(&mut self) {
+        let mut rock: Vec<u8> = ROCKS[self.rock_idx].to_vec();
+        self.rock_idx = (self.rock_idx + 1) % ROCKS.len();
+        let mut bottom = self.rows.len() + 3;
+
+        loop {
+            let jet = self.jets[self.jet_idx];
+            self.jet_idx = (self.jet_idx + 1) % self.jets.len();
+            self.push(&mut rock, bottom, jet);
+
+            if bottom == 0 || self.collides(&rock, bottom - 1) {
+                break;
+            }
+            bottom -= 1;
+        }
+
+        for (dy, r) in rock.iter().enumerate() {
+            match self.rows.get_mut(bottom + dy) {
+                Some(row) => *row |= r,
+                None => self.rows.push(*r),
+            }
+        }
+        self.rocks_dropped += 1;
+    }
+
+    pub fn state(&self) -> ChamberState {
+        let mut surface = [self.rows.len(); CHAMBER_WIDTH];
+        for (col, depth) in surface.iter_mut().enumerate() {
+            let bit = LEFT_WALL >> col;
+            if let Some(d) = self.rows.iter().rev().position(|row| row & bit != 0) {
+                *depth = d;
+            }
+        }
+        ChamberState {
+            rock_idx: self.rock_idx,
+            jet_idx: self.jet_idx,
+            surface,
+        }
+    }
+}
+
+/// Draws the chamber the way the puzzle does, top row first.
+impl Display for Chamber<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows.iter().rev() {
+            write!(f, "|")?;
+            for col in 0..CHAMBER_WIDTH {
+                let filled = row & (LEFT_WALL >> col) != 0;
+                write!(f, "{}", if filled { '#' } else { '.' })?;
+            }
+            writeln!(f, "|")?;
+        }
+        writeln!(f, "+{}+", "-".repeat(CHAMBER_WIDTH))
+    }
+}
+
+/// Height of the tower after `rocks` rocks. Once a chamber state repeats, the rocks in
+/// between are a cycle that adds the same height every time, so whole cycles are skipped.
+/// If `rocks` rocks fall before any state repeats, that's just the height they reached.
+pub fn tower_height(jets: &[Jet], rocks: u64) -> u64 {
+    let mut chamber = Chamber::new(jets);
+    let found = find_cycle_hashed_within(
+        (0, chamber.state()),
+        |_| {
+            chamber.drop_rock();
+            (chamber.height(), chamber.state())
+        },
+        |(_, state)| state.clone(),
+        usize::try_from(rocks).unwrap_or(usize::MAX),
+    );
+    match found {
+        Ok(cycle) => cycle.extrapolate(rocks, |(height, _)| *height as i64) as u64,
+        Err(history) => history.last().unwrap().0,
+    }
+}
 
 #[aoc(2022, day17)]
 pub mod solutions {
     use super::*;
 
     #[generator(gen)]
-    pub fn input_generator(input: &str) -> Vec<i32> {
-        Vec::new()
+    pub fn input_generator(input: &str) -> Vec<Jet> {
+        input
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '<' => Jet::Left,
+                '>' => Jet::Right,
+                _ => panic!("Unknown jet direction: '{}'", c),
+            })
+            .collect()
     }
 
     // ----------------------- Part 1 -----------------------
 
-    #[solver(part1, draft_solvr)]
-    pub fn solve_part1(input: Vec<i32>) -> u32 {
-        0
+    #[solver(part1, simulate)]
+    pub fn solve_part1(input: Vec<Jet>) -> u64 {
+        let mut chamber = Chamber::new(&input);
+        for _ in 0..2022 {
+            chamber.drop_rock();
+        }
+        chamber.height()
     }
 
-    #[solution(part1, draft_soln)]
-    pub fn part1_draft(input: &str) -> u32 {
-        0
+    #[solver(part1, cycle)]
+    pub fn solve_part1_cycle(input: Vec<Jet>) -> u64 {
+        tower_height(&input, 2022)
     }
 
     // ----------------------- Part 2 -----------------------
 
-    #[solver(part2, draft_solvr)]
-    pub fn solve_part2(input: Vec<i32>) -> u32 {
-        0
-    }
-
-    #[solution(part2, draft_soln)]
-    pub fn part2_draft(input: &str) -> u32 {
-        0
+    #[solver(part2, cycle)]
+    pub fn solve_part2(input: Vec<Jet>) -> u64 {
+        tower_height(&input, 1_000_000_000_000)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use aoc_zen_runner_macros::aoc_case;
+
     use super::solutions::*;
+    use super::*;
+
+    #[aoc_case(3068, 1514285714288)]
+    const INPUT1: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
 
-    #[aoc_case(0, 0)]
-    const input1: &str = "";
+    #[test]
+    fn test_first_rocks() {
+        let jets = input_generator(INPUT1);
+        let mut chamber = Chamber::new(&jets);
+        chamber.drop_rock();
+        assert_eq!(chamber.to_string(), "|..####.|\n+-------+\n");
+        chamber.drop_rock();
+        chamber.drop_rock();
+        assert_eq!(
+            chamber.to_string(),
+            "\
+|..#....|
+|..#....|
+|####...|
+|..###..|
+|...#...|
+|..####.|
++-------+
+"
+        );
+        assert_eq!(chamber.state().surface, [2, 2, 0, 2, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_cycle_matches_simulation() {
+        let jets = input_generator(INPUT1);
+        let mut chamber = Chamber::new(&jets);
+        assert_eq!(tower_height(&jets, 0), 0);
+        for rocks in 1..=500 {
+            chamber.drop_rock();
+            assert_eq!(
+                tower_height(&jets, rocks),
+                chamber.height(),
+                "{} rocks",
+                rocks
+            );
+        }
+    }
 }
@@ -1,12 +1,10 @@
+use advent_of_code_2022::voxels::{Droplet, VoxelGrid};
 use aoc_zen_runner_macros::{aoc, generator, solver};
 use itertools::{Itertools, MinMaxResult};
 use rayon::prelude::*;
 #[allow(unused_imports)]
 use std::cmp::max;
-use std::{
-    collections::{HashSet, VecDeque},
-    sync::atomic::{AtomicI64, Ordering},
-};
+use std::collections::{HashSet, VecDeque};
 
 #[aoc(2022, day18)]
 pub mod solutions {
@@ -92,6 +90,26 @@ pub mod solutions {
             .sum()
     }
 
+    #[solver(part1, voxels)]
+    pub fn solve_part1_voxels(input: GenData) -> OutData {
+        Droplet::new(input).surface_area()
+    }
+
+    #[solver(part2, voxels)]
+    pub fn solve_part2_voxels(input: GenData) -> OutData {
+        Droplet::new(input).exterior_area()
+    }
+
+    #[solver(part1, bitgrid)]
+    pub fn solve_part1_bitgrid(input: GenData) -> OutData {
+        VoxelGrid::new(&input).surface_area()
+    }
+
+    #[solver(part2, bitgrid)]
+    pub fn solve_part2_bitgrid(input: GenData) -> OutData {
+        VoxelGrid::new(&input).exterior_area()
+    }
+
     #[solver(part2, traverse)]
     pub fn solve_part2_traversal(input: GenData) -> OutData {
         let points: HashSet<(i64, i64, i64)> = input.iter().cloned().collect();
@@ -113,14 +131,10 @@ pub mod solutions {
 
         let mut explored: HashSet<(i64, i64, i64)> = HashSet::new();
 
-        let surface = AtomicI64::new(0);
+        let mut surface = 0;
 
-        let max_count = (x_max + 2) * (y_max + 2) * (z_max + 2);
-        println!(
-            "Maximum space: [X: {}..={}]  [Y: {}..={}]  [Z: {}..={}]  [Total: {}]",
-            x_min, x_max, y_min, y_max, z_min, z_max, max_count
-        );
-        let seen = AtomicI64::new(0);
+        let max_count = (x_max - x_min + 3) * (y_max - y_min + 3) * (z_max - z_min + 3);
+        let mut seen = 0;
 
         while let Some(pt @ (x, y, z)) = work_queue.pop_front() {
             if explored.contains(&pt) {
@@ -132,7 +146,8 @@ pub mod solutions {
             //     println!("Explored point {:?}", pt);
             // }
 
-            let current_seen = seen.fetch_add(1, Ordering::AcqRel);
+            let current_seen = seen;
+            seen += 1;
             // if current_seen % 100 == 0 {
             //     println!("{} nodes explored", current_seen);
             // }
@@ -140,11 +155,10 @@ pub mod solutions {
                 panic!("Explored too many nodes, there must be an infinite loop.")
             }
 
-            let surface_seen = neighbors(x, y, z)
+            surface += neighbors(x, y, z)
                 .into_iter()
                 .filter(|pt| points.contains(pt))
-                .count() as i64;
-            surface.fetch_add(surface_seen, Ordering::Relaxed);
+                .count();
             work_queue.extend(
                 neighbors(x, y, z)
                     .into_iter()
@@ -157,7 +171,7 @@ pub mod solutions {
             );
         }
 
-        surface.into_inner() as usize
+        surface
     }
 }
 
@@ -240,5 +254,20 @@ pub mod test {
 3,3,3
 "#;
         assert_eq!(solve_part2_traversal(input_generator(points2)), 54);
+        assert_eq!(solve_part2_voxels(input_generator(points2)), 54);
+        assert_eq!(solve_part2_bitgrid(input_generator(points2)), 54);
+    }
+
+    #[test]
+    fn test_traversal_away_from_origin() {
+        // The traversal gives up after visiting more voxels than its padded bounding box
+        // holds, which must not depend on where the box is.
+        for offset in [-40, 40] {
+            let shifted = input_generator(test_in)
+                .into_iter()
+                .map(|(x, y, z)| (x + offset, y + offset, z + offset))
+                .collect();
+            assert_eq!(solve_part2_traversal(shifted), 58, "offset {}", offset);
+        }
     }
 }
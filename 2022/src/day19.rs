@@ -1,44 +1,311 @@
-use aoc_zen_runner_macros::{aoc, generator, solution, solver};
+use std::fmt::Display;
+
+use aoc_zen_runner_macros::{aoc, generator, solver};
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Robot {
+    Ore = 0,
+    Clay = 1,
+    Obsidian = 2,
+    Geode = 3,
+}
+
+const ROBOTS: [Robot; 4] = [Robot::Ore, Robot::Clay, Robot::Obsidian, Robot::Geode];
+
+impl Display for Robot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Robot::Ore => "ore",
+            Robot::Clay => "clay",
+            Robot::Obsidian => "obsidian",
+            Robot::Geode => "geode",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Counts of each resource, or of each kind of robot, indexed by `Robot as usize`.
+pub type Amounts = [u32; 4];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blueprint {
+    pub id: u32,
+    /// What each kind of robot costs, indexed by `Robot as usize` twice.
+    pub costs: [Amounts; 4],
+}
+
+impl Blueprint {
+    /// Parse one blueprint from its text. Only the numbers are read, in the order the puzzle
+    /// always gives them, so line breaks inside a blueprint don't matter.
+    pub fn parse(text: &str) -> Blueprint {
+        let nums: Vec<u32> = text
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let [id, ore_ore, clay_ore, obsidian_ore, obsidian_clay, geode_ore, geode_obsidian] =
+            nums[..]
+        else {
+            panic!("Expected 7 numbers in blueprint, found {:?}", text.trim());
+        };
+        Blueprint {
+            id,
+            costs: [
+                [ore_ore, 0, 0, 0],
+                [clay_ore, 0, 0, 0],
+                [obsidian_ore, obsidian_clay, 0, 0],
+                [geode_ore, 0, geode_obsidian, 0],
+            ],
+        }
+    }
+
+    /// The most robots of each kind worth having: one robot's output covers one build a
+    /// minute, and only one robot can be built per minute. Geode robots are never too many.
+    fn max_useful(&self) -> Amounts {
+        let mut max = [0, 0, 0, u32::MAX];
+        for cost in &self.costs {
+            for r in 0..3 {
+                max[r] = max[r].max(cost[r]);
+            }
+        }
+        max
+    }
+
+    /// Replay a build order minute by minute. Each entry is the minute (from 1) construction
+    /// starts and the robot built. Returns the geodes opened, or `None` if the order builds
+    /// something it can't afford, builds twice in a minute, or runs past the time limit.
+    pub fn replay(&self, minutes: u32, builds: &[(u32, Robot)]) -> Option<u32> {
+        let mut robots: Amounts = [1, 0, 0, 0];
+        let mut resources: Amounts = [0; 4];
+        let mut builds = builds.iter().peekable();
+        for minute in 1..=minutes {
+            let building = builds.next_if(|(m, _)| *m == minute).map(|(_, r)| *r);
+            if let Some(r) = building {
+                let cost = self.costs[r as usize];
+                if (0..4).any(|i| resources[i] < cost[i]) {
+                    return None;
+                }
+                (0..4).for_each(|i| resources[i] -= cost[i]);
+            }
+            (0..4).for_each(|i| resources[i] += robots[i]);
+            if let Some(r) = building {
+                robots[r as usize] += 1;
+            }
+        }
+        builds
+            .next()
+            .is_none()
+            .then_some(resources[Robot::Geode as usize])
+    }
+}
+
+/// The most geodes one blueprint can open, and a build order that does it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub geodes: u32,
+    pub builds: Vec<(u32, Robot)>,
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} geodes", self.geodes)?;
+        for (minute, robot) in &self.builds {
+            writeln!(f, "  minute {}: build {} robot", minute, robot)?;
+        }
+        Ok(())
+    }
+}
+
+/// Depth-first branch and bound over "which robot to build next". Waiting is folded into
+/// each choice, so every node is a build.
+struct Search<'a> {
+    blueprint: &'a Blueprint,
+    minutes: u32,
+    max_useful: Amounts,
+    path: Vec<(u32, Robot)>,
+    best: Plan,
+}
+
+impl Search<'_> {
+    /// Geodes opened if, from now on, an obsidian robot and (when obsidian allows) a geode
+    /// robot were built every minute for free otherwise. Never less than what's reachable.
+    fn upper_bound(&self, time_left: u32, robots: &Amounts, resources: &Amounts) -> u32 {
+        let geode_cost = self.blueprint.costs[Robot::Geode as usize][Robot::Obsidian as usize];
+        let (mut obsidian, mut obsidian_robots) = (resources[2], robots[2]);
+        let (mut geodes, mut geode_robots) = (resources[3], robots[3]);
+        for _ in 0..time_left {
+            let build_geode = obsidian >= geode_cost;
+            if build_geode {
+                obsidian -= geode_cost;
+            }
+            obsidian += obsidian_robots;
+            geodes += geode_robots;
+            obsidian_robots += 1;
+            geode_robots += build_geode as u32;
+        }
+        geodes
+    }
+
+    fn search(&mut self, time_left: u32, robots: Amounts, resources: Amounts) {
+        let geode = Robot::Geode as usize;
+        let idle = resources[geode] + robots[geode] * time_left;
+        if idle > self.best.geodes {
+            self.best = Plan {
+                geodes: idle,
+                builds: self.path.clone(),
+            };
+        }
+        if self.upper_bound(time_left, &robots, &resources) <= self.best.geodes {
+            return;
+        }
+
+        for robot in ROBOTS.into_iter().rev() {
+            let r = robot as usize;
+            if robots[r] >= self.max_useful[r] {
+                continue;
+            }
+            let cost = &self.blueprint.costs[r];
+            let mut wait = 0;
+            let buildable = (0..3).all(|i| {
+                if cost[i] <= resources[i] {
+                    true
+                } else if robots[i] == 0 {
+                    false
+                } else {
+                    wait = wait.max((cost[i] - resources[i]).div_ceil(robots[i]));
+                    true
+                }
+            });
+            // A robot finished in the last minute can't produce anything.
+            if !buildable || wait + 1 >= time_left {
+                continue;
+            }
+
+            let elapsed = wait + 1;
+            let mut next_resources = resources;
+            for i in 0..4 {
+                next_resources[i] = resources[i] + robots[i] * elapsed - cost[i];
+            }
+            let mut next_robots = robots;
+            next_robots[r] += 1;
+
+            self.path.push((self.minutes - time_left + wait + 1, robot));
+            self.search(time_left - elapsed, next_robots, next_resources);
+            self.path.pop();
+        }
+    }
+}
+
+/// Best plan for a blueprint over `minutes`, starting with one ore robot.
+pub fn best_plan(blueprint: &Blueprint, minutes: u32) -> Plan {
+    let mut search = Search {
+        blueprint,
+        minutes,
+        max_useful: blueprint.max_useful(),
+        path: vec![],
+        best: Plan {
+            geodes: 0,
+            builds: vec![],
+        },
+    };
+    search.search(minutes, [1, 0, 0, 0], [0; 4]);
+    search.best
+}
 
 #[aoc(2022, day19)]
 pub mod solutions {
     use super::*;
 
     #[generator(gen)]
-    pub fn input_generator(input: &str) -> Vec<i32> {
-        Vec::new()
+    pub fn input_generator(input: &str) -> Vec<Blueprint> {
+        input
+            .split("Blueprint")
+            .filter(|b| !b.trim().is_empty())
+            .map(Blueprint::parse)
+            .collect()
     }
 
     // ----------------------- Part 1 -----------------------
 
-    #[solver(part1, draft_solvr)]
-    pub fn solve_part1(input: Vec<i32>) -> u32 {
-        0
-    }
-
-    #[solution(part1, draft_soln)]
-    pub fn part1_draft(input: &str) -> u32 {
-        0
+    #[solver(part1, bnb)]
+    pub fn solve_part1(input: Vec<Blueprint>) -> u32 {
+        input
+            .par_iter()
+            .map(|bp| bp.id * best_plan(bp, 24).geodes)
+            .sum()
     }
 
     // ----------------------- Part 2 -----------------------
 
-    #[solver(part2, draft_solvr)]
-    pub fn solve_part2(input: Vec<i32>) -> u32 {
-        0
-    }
-
-    #[solution(part2, draft_soln)]
-    pub fn part2_draft(input: &str) -> u32 {
-        0
+    #[solver(part2, bnb)]
+    pub fn solve_part2(input: Vec<Blueprint>) -> u32 {
+        input
+            .par_iter()
+            .take(3)
+            .map(|bp| best_plan(bp, 32).geodes)
+            .product()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use aoc_zen_runner_macros::aoc_case;
+
     use super::solutions::*;
+    use super::*;
+
+    #[aoc_case(33, 3472)]
+    const INPUT1: &str = "
+Blueprint 1:
+  Each ore robot costs 4 ore.
+  Each clay robot costs 2 ore.
+  Each obsidian robot costs 3 ore and 14 clay.
+  Each geode robot costs 2 ore and 7 obsidian.
+
+Blueprint 2:
+  Each ore robot costs 2 ore.
+  Each clay robot costs 3 ore.
+  Each obsidian robot costs 3 ore and 8 clay.
+  Each geode robot costs 3 ore and 12 obsidian.
+";
 
-    #[aoc_case(0, 0)]
-    const input1: &str = "";
+    #[test]
+    fn test_parse() {
+        let blueprints = input_generator(INPUT1);
+        assert_eq!(blueprints.len(), 2);
+        assert_eq!(blueprints[1].id, 2);
+        assert_eq!(
+            blueprints[0].costs,
+            [[4, 0, 0, 0], [2, 0, 0, 0], [3, 14, 0, 0], [2, 0, 7, 0]]
+        );
+
+        let one_line = "Blueprint 7: Each ore robot costs 4 ore. Each clay robot costs 4 ore. \
+            Each obsidian robot costs 4 ore and 9 clay. Each geode robot costs 3 ore and 9 obsidian.";
+        assert_eq!(input_generator(one_line)[0].costs[3], [3, 0, 9, 0]);
+    }
+
+    #[test]
+    fn test_plan_witness() {
+        let blueprints = input_generator(INPUT1);
+        for (bp, geodes) in blueprints.iter().zip([9, 12]) {
+            let plan = best_plan(bp, 24);
+            assert_eq!(plan.geodes, geodes);
+            assert_eq!(bp.replay(24, &plan.builds), Some(geodes));
+        }
+
+        // The build order from the puzzle text's walkthrough of blueprint 1.
+        let walkthrough = [
+            (3, Robot::Clay),
+            (5, Robot::Clay),
+            (7, Robot::Clay),
+            (11, Robot::Obsidian),
+            (12, Robot::Clay),
+            (15, Robot::Obsidian),
+            (18, Robot::Geode),
+            (21, Robot::Geode),
+        ];
+        assert_eq!(blueprints[0].replay(24, &walkthrough), Some(9));
+        assert_eq!(blueprints[0].replay(24, &[(1, Robot::Clay)]), None);
+    }
 }
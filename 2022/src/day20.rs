@@ -1,27 +1,43 @@
+use advent_of_code_2022::circular::CircularList;
+use aoc_common::params;
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, solution};
 use itertools::Itertools;
 
+puzzle_params! {
+    pub struct Params {
+        decryption_key: i64 = 811589153,
+        part2_rounds: u32 = 10,
+    }
+}
+
 #[aoc(2022, day20)]
 pub mod solutions {
     pub type GenData = Vec<i32>;
     pub type OutData = i64;
 
     use super::*;
-    fn mix(input: &[i64], num_rounds: u32) -> Vec<i64> {
-        let mut input = input.iter().enumerate().collect_vec();
+    pub fn mix(input: &[i64], num_rounds: u32) -> CircularList<i64> {
+        let mut list: CircularList<i64> = input.iter().copied().collect();
         for _ in 0..num_rounds {
-            for old_idx in 0..input.len() {
-                let (curr_idx, _) = input
-                    .iter()
-                    .find_position(|(oi, _)| *oi == old_idx)
-                    .unwrap();
-                let elem = input.remove(curr_idx);
-                let new_idx = (curr_idx as i64 + elem.1).rem_euclid(input.len() as i64);
-                input.insert(new_idx as usize, elem)
+            for id in 0..list.len() {
+                let k = *list.get(id);
+                list.move_by(id, k);
             }
         }
 
-        input.into_iter().map(|(_, val)| *val).collect_vec()
+        list
+    }
+
+    fn grove_coordinates(mixed: &CircularList<i64>) -> i64 {
+        let zero_id = (0..mixed.len())
+            .find(|&id| *mixed.get(id) == 0)
+            .unwrap_or_else(|| panic!("No 0 in the file"));
+
+        [1000, 2000, 3000]
+            .iter()
+            .map(|&offset| mixed.after(zero_id, offset))
+            .sum()
     }
 
     #[solution(part1, draft)]
@@ -34,39 +50,32 @@ pub mod solutions {
 
         let mixed = mix(&input, 1);
 
-        let (zero_idx, _) = mixed.iter().find_position(|i| **i == 0).unwrap();
-
-        let first_var = mixed[(zero_idx + 1000).rem_euclid(input.len()) as usize];
-        let second_var = mixed[(zero_idx + 2000).rem_euclid(input.len()) as usize];
-        let third_var = mixed[(zero_idx + 3000).rem_euclid(input.len()) as usize];
-
-        first_var + second_var + third_var
+        grove_coordinates(&mixed)
     }
 
     #[solution(part2, draft)]
     pub fn solve_part2(input: &str) -> OutData {
+        let (params, input): (Params, &str) = params::load(input);
         let input = input
             .lines()
             .filter(|ln| ln.trim() != "")
             .map(|ln| ln.trim().parse::<i64>().unwrap())
-            .map(|x| x * 811589153)
+            .map(|x| x * params.decryption_key)
             .collect_vec();
 
-        let mixed = mix(&input, 10);
-
-        let (zero_idx, _) = mixed.iter().find_position(|i| **i == 0).unwrap();
+        let mixed = mix(&input, params.part2_rounds);
 
-        let first_var = mixed[(zero_idx + 1000).rem_euclid(input.len()) as usize];
-        let second_var = mixed[(zero_idx + 2000).rem_euclid(input.len()) as usize];
-        let third_var = mixed[(zero_idx + 3000).rem_euclid(input.len()) as usize];
-
-        first_var + second_var + third_var
+        grove_coordinates(&mixed)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use advent_of_code_2022::testutil::lcg;
     use aoc_zen_runner_macros::aoc_case;
+    use itertools::Itertools;
+
+    use super::solutions::mix;
 
     #[aoc_case(3, 1623178306)]
     const test_in: &str = r#"
@@ -78,4 +87,32 @@ mod test {
 0
 4
 "#;
+
+    #[test]
+    fn test_mix_matches_vec() {
+        // The original `Vec::remove`/`insert` mixing, kept as a reference.
+        fn mix_vec(input: &[i64], num_rounds: u32) -> Vec<i64> {
+            let mut input = input.iter().enumerate().collect_vec();
+            for _ in 0..num_rounds {
+                for old_idx in 0..input.len() {
+                    let (curr_idx, _) = input
+                        .iter()
+                        .find_position(|(oi, _)| *oi == old_idx)
+                        .unwrap();
+                    let elem = input.remove(curr_idx);
+                    let new_idx = (curr_idx as i64 + elem.1).rem_euclid(input.len() as i64);
+                    input.insert(new_idx as usize, elem)
+                }
+            }
+
+            input.into_iter().map(|(_, val)| *val).collect_vec()
+        }
+
+        let mut rand = lcg(20);
+        let input: Vec<i64> = (0..500).map(|_| rand(1001) as i64 - 500).collect();
+        for rounds in [1, 3] {
+            let mixed = mix(&input, rounds);
+            assert_eq!(mixed.iter().copied().collect_vec(), mix_vec(&input, rounds));
+        }
+    }
 }
@@ -0,0 +1,412 @@
+use std::cmp::{max, min};
+use std::collections::btree_map::{self, BTreeMap};
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+
+/// Integer-like values with a well-defined next and previous value.
+pub trait Discrete: Ord + Copy {
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    /// Number of values in `lo..=hi`, saturating at `u64::MAX`.
+    fn span(lo: Self, hi: Self) -> u64;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn span(lo: Self, hi: Self) -> u64 {
+                    let width = (hi as i128) - (lo as i128) + 1;
+                    u64::try_from(width).unwrap_or(u64::MAX)
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// A set of integers stored as sorted, disjoint, non-adjacent inclusive ranges.
+///
+/// Ranges are kept in a `BTreeMap` from start to end, so inserting, removing and looking up a
+/// point are all `O(log n)` plus the cost of whatever ranges get merged or split.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: BTreeMap<T, T>,
+}
+
+impl<T: Discrete> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (mut lo, mut hi) = range.into_inner();
+        if lo > hi {
+            return;
+        }
+
+        // A range starting at or before `lo` may overlap or touch the new one.
+        if let Some((&s, &e)) = self.ranges.range(..=lo).next_back() {
+            if e >= lo || e.succ() == Some(lo) {
+                self.ranges.remove(&s);
+                lo = s;
+                hi = max(hi, e);
+            }
+        }
+
+        // Anything starting inside the new range, or right after it, gets swallowed.
+        loop {
+            let next = match hi.succ() {
+                Some(after) => self.ranges.range(lo..=after).next(),
+                None => self.ranges.range(lo..).next(),
+            };
+            let Some((&s, &e)) = next else {
+                break;
+            };
+            self.ranges.remove(&s);
+            hi = max(hi, e);
+        }
+
+        self.ranges.insert(lo, hi);
+    }
+
+    pub fn remove(&mut self, range: RangeInclusive<T>) {
+        let (lo, hi) = range.into_inner();
+        if lo > hi {
+            return;
+        }
+
+        if let Some((&s, &e)) = self.ranges.range(..lo).next_back() {
+            if e >= lo {
+                // `s < lo`, so `lo` has a predecessor.
+                self.ranges.insert(s, lo.pred().unwrap());
+                if e > hi {
+                    self.ranges.insert(hi.succ().unwrap(), e);
+                    return;
+                }
+            }
+        }
+
+        let inside: Vec<(T, T)> = self.ranges.range(lo..=hi).map(|(&s, &e)| (s, e)).collect();
+        for (s, e) in inside {
+            self.ranges.remove(&s);
+            if e > hi {
+                self.ranges.insert(hi.succ().unwrap(), e);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    pub fn contains(&self, point: T) -> bool {
+        self.range_containing(point).is_some()
+    }
+
+    pub fn range_containing(&self, point: T) -> Option<RangeInclusive<T>> {
+        self.ranges
+            .range(..=point)
+            .next_back()
+            .filter(|(_, &e)| e >= point)
+            .map(|(&s, &e)| s..=e)
+    }
+
+    /// True if every point of `range` is in the set.
+    pub fn contains_range(&self, range: &RangeInclusive<T>) -> bool {
+        range.is_empty()
+            || self
+                .range_containing(*range.start())
+                .is_some_and(|r| r.end() >= range.end())
+    }
+
+    /// Number of points in the set, saturating at `u64::MAX`.
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .fold(0u64, |acc, (&s, &e)| acc.saturating_add(T::span(s, e)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn ranges_count(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter().map(|(&s, &e)| s..=e)
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let (mut big, small) = if self.ranges.len() >= other.ranges.len() {
+            (self.clone(), other)
+        } else {
+            (other.clone(), self)
+        };
+        big.extend(small.iter());
+        big
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut results = IntervalSet::new();
+        let mut a = self.ranges.iter().peekable();
+        let mut b = other.ranges.iter().peekable();
+
+        while let (Some(&(&a_s, &a_e)), Some(&(&b_s, &b_e))) = (a.peek(), b.peek()) {
+            let lo = max(a_s, b_s);
+            let hi = min(a_e, b_e);
+            if lo <= hi {
+                // Both inputs are already disjoint and non-adjacent, so the pieces are too.
+                results.ranges.insert(lo, hi);
+            }
+            if a_e < b_e {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        results
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut results = self.clone();
+        for r in other.iter() {
+            if results.is_empty() {
+                break;
+            }
+            results.remove(r);
+        }
+        results
+    }
+
+    /// Everything in `bounds` that is not in the set.
+    pub fn complement(&self, bounds: RangeInclusive<T>) -> IntervalSet<T> {
+        IntervalSet {
+            ranges: self.gaps(bounds).map(|r| r.into_inner()).collect(),
+        }
+    }
+
+    /// The maximal runs inside `bounds` that are not in the set, in order.
+    pub fn gaps(&self, bounds: RangeInclusive<T>) -> Gaps<'_, T> {
+        let (lo, hi) = bounds.into_inner();
+        let before = self
+            .ranges
+            .range(..lo)
+            .next_back()
+            .map(|(&s, &e)| (s, e))
+            .filter(|(_, e)| *e >= lo);
+        // With empty bounds the cursor starts out finished, so `rest` is never read.
+        Gaps {
+            before,
+            rest: self.ranges.range(lo..=max(lo, hi)),
+            cursor: (lo <= hi).then_some(lo),
+            hi,
+        }
+    }
+}
+
+pub struct Gaps<'a, T> {
+    before: Option<(T, T)>,
+    rest: btree_map::Range<'a, T, T>,
+    cursor: Option<T>,
+    hi: T,
+}
+
+impl<T: Discrete> Iterator for Gaps<'_, T> {
+    type Item = RangeInclusive<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let cursor = self.cursor?;
+            let next = self
+                .before
+                .take()
+                .or_else(|| self.rest.next().map(|(&s, &e)| (s, e)));
+            match next {
+                Some((s, e)) => {
+                    self.cursor = min(e, self.hi).succ().filter(|c| *c <= self.hi);
+                    if cursor < s {
+                        return Some(cursor..=s.pred().unwrap());
+                    }
+                }
+                None => {
+                    self.cursor = None;
+                    return Some(cursor..=self.hi);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Discrete> Extend<RangeInclusive<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = RangeInclusive<T>>>(&mut self, iter: I) {
+        for r in iter {
+            self.insert(r);
+        }
+    }
+}
+
+impl<T: Discrete> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut results = IntervalSet::new();
+        results.extend(iter);
+        results
+    }
+}
+
+impl<T: Discrete> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let mut results = IntervalSet::new();
+        results.insert(range);
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::lcg;
+    use std::collections::BTreeSet;
+
+    fn ranges<T: Discrete>(set: &IntervalSet<T>) -> Vec<RangeInclusive<T>> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn test_insert_merges() {
+        let mut set = IntervalSet::new();
+        set.insert(1..=3);
+        set.insert(7..=9);
+        assert_eq!(ranges(&set), vec![1..=3, 7..=9]);
+        set.insert(4..=5);
+        assert_eq!(ranges(&set), vec![1..=5, 7..=9]);
+        set.insert(6..=6);
+        assert_eq!(ranges(&set), vec![1..=9]);
+        set.insert(-4..=20);
+        assert_eq!(ranges(&set), vec![-4..=20]);
+        let (lo, hi) = (5, 4);
+        set.insert(lo..=hi);
+        assert_eq!(set.len(), 25);
+    }
+
+    #[test]
+    fn test_remove_splits() {
+        let mut set: IntervalSet<i64> = (0..=10).into();
+        set.remove(5..=5);
+        assert_eq!(ranges(&set), vec![0..=4, 6..=10]);
+        set.remove(3..=7);
+        assert_eq!(ranges(&set), vec![0..=2, 8..=10]);
+        set.remove(-5..=0);
+        set.remove(10..=50);
+        assert_eq!(ranges(&set), vec![1..=2, 8..=9]);
+        assert!(!set.contains(0));
+        assert!(set.contains(8));
+        assert_eq!(set.range_containing(9), Some(8..=9));
+    }
+
+    #[test]
+    fn test_domain_edges() {
+        let mut set: IntervalSet<u8> = (250..=255).into();
+        set.insert(0..=3);
+        set.insert(4..=249);
+        assert_eq!(ranges(&set), vec![0..=255]);
+        assert_eq!(set.len(), 256);
+        set.remove(255..=255);
+        set.remove(0..=0);
+        assert_eq!(ranges(&set), vec![1..=254]);
+        assert_eq!(ranges(&set.complement(0..=255)), vec![0..=0, 255..=255]);
+
+        let full: IntervalSet<u64> = (0..=u64::MAX).into();
+        assert_eq!(full.len(), u64::MAX);
+        assert!(full.complement(0..=u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: IntervalSet<i32> = [0..=5, 10..=15, 20..=25].into_iter().collect();
+        let b: IntervalSet<i32> = [3..=12, 24..=30].into_iter().collect();
+
+        assert_eq!(ranges(&a.union(&b)), vec![0..=15, 20..=30]);
+        assert_eq!(ranges(&a.intersection(&b)), vec![3..=5, 10..=12, 24..=25]);
+        assert_eq!(ranges(&a.difference(&b)), vec![0..=2, 13..=15, 20..=23]);
+        assert_eq!(ranges(&b.difference(&a)), vec![6..=9, 26..=30]);
+        assert_eq!(
+            ranges(&a.complement(-2..=22)),
+            vec![-2..=-1, 6..=9, 16..=19]
+        );
+        assert_eq!(a.gaps(11..=14).count(), 0);
+        assert_eq!(a.gaps(7..=8).collect::<Vec<_>>(), vec![7..=8]);
+        assert!(a.contains_range(&(11..=14)));
+        assert!(!a.contains_range(&(4..=11)));
+    }
+
+    fn random_ops(rand: &mut impl FnMut(u64) -> u64) -> (IntervalSet<i16>, BTreeSet<i16>) {
+        let mut set = IntervalSet::new();
+        let mut model = BTreeSet::new();
+        for _ in 0..40 {
+            let a = rand(100) as i16 - 10;
+            let b = a + rand(12) as i16;
+            if rand(3) == 0 {
+                set.remove(a..=b);
+                model.retain(|x| !(a..=b).contains(x));
+            } else {
+                set.insert(a..=b);
+                model.extend(a..=b);
+            }
+        }
+        (set, model)
+    }
+
+    fn check_against(set: &IntervalSet<i16>, model: &BTreeSet<i16>) {
+        assert_eq!(set.len(), model.len() as u64);
+        for x in -20..=120 {
+            assert_eq!(set.contains(x), model.contains(&x), "point {}", x);
+        }
+        // Ranges must stay sorted, disjoint and non-adjacent.
+        for (a, b) in set.iter().zip(set.iter().skip(1)) {
+            assert!(a.end() + 1 < *b.start(), "{:?} then {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_against_btreeset() {
+        let mut rand = lcg(0x5eed);
+        for _ in 0..50 {
+            let (a, a_model) = random_ops(&mut rand);
+            let (b, b_model) = random_ops(&mut rand);
+            check_against(&a, &a_model);
+
+            let union = a_model.union(&b_model).copied().collect();
+            let inter = a_model.intersection(&b_model).copied().collect();
+            let diff = a_model.difference(&b_model).copied().collect();
+            check_against(&a.union(&b), &union);
+            check_against(&a.intersection(&b), &inter);
+            check_against(&a.difference(&b), &diff);
+
+            let compl = (-5..=105).filter(|x| !a_model.contains(x)).collect();
+            check_against(&a.complement(-5..=105), &compl);
+            let gap_points: BTreeSet<i16> = a.gaps(-5..=105).flatten().collect();
+            assert_eq!(gap_points, compl);
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod circular;
+pub mod cycles;
+pub mod intervals;
+#[doc(hidden)]
+pub mod testutil;
+pub mod voxels;
@@ -0,0 +1,14 @@
+//! Helpers for tests. The day binaries' tests use them too, and those can't see anything
+//! behind `#[cfg(test)]` in this library, so they're always compiled.
+
+/// A small seeded linear congruential generator, so randomised tests are reproducible without
+/// a `rand` dependency. Each call gives a value below its argument.
+pub fn lcg(seed: u64) -> impl FnMut(u64) -> u64 {
+    let mut state = seed;
+    move |n| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) % n
+    }
+}
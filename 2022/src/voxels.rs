@@ -0,0 +1,627 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+pub type Voxel = (i64, i64, i64);
+
+/// Unit steps to the six face-adjacent voxels, in the order `-x, +x, -y, +y, -z, +z`.
+pub const DIRECTIONS: [Voxel; 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+pub fn neighbors((x, y, z): Voxel) -> [Voxel; 6] {
+    DIRECTIONS.map(|(dx, dy, dz)| (x + dx, y + dy, z + dz))
+}
+
+/// One unit square on the surface: the side of `cube` facing `DIRECTIONS[dir]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Face {
+    pub cube: Voxel,
+    pub dir: usize,
+}
+
+impl Face {
+    /// Corners counter-clockwise as seen from outside the cube, so the winding gives an
+    /// outward normal.
+    pub fn corners(&self) -> [Voxel; 4] {
+        let axis = self.dir / 2;
+        let positive = self.dir % 2 == 1;
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let base = [self.cube.0, self.cube.1, self.cube.2];
+
+        let corner = |du: i64, dv: i64| {
+            let mut c = base;
+            c[axis] += positive as i64;
+            c[u] += du;
+            c[v] += dv;
+            (c[0], c[1], c[2])
+        };
+        let ccw = [corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1)];
+        if positive {
+            ccw
+        } else {
+            [ccw[0], ccw[3], ccw[2], ccw[1]]
+        }
+    }
+
+    pub fn normal(&self) -> Voxel {
+        DIRECTIONS[self.dir]
+    }
+}
+
+/// A face-connected group of cubes and how its surface splits between the open air outside
+/// the droplet and air pockets sealed inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub cubes: Vec<Voxel>,
+    pub exterior_area: usize,
+    pub interior_area: usize,
+}
+
+/// A face-connected region of air that can't reach the outside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AirPocket {
+    pub cells: Vec<Voxel>,
+}
+
+impl AirPocket {
+    pub fn volume(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// A set of unit cubes, with the air around it classified into outside air and sealed
+/// pockets. Both are found once, when the droplet is built.
+#[derive(Debug, Clone)]
+pub struct Droplet {
+    cubes: HashSet<Voxel>,
+    /// Air reachable from beyond the bounding box, within one voxel of it.
+    outside: HashSet<Voxel>,
+    min: Voxel,
+    max: Voxel,
+}
+
+fn flood(start: Voxel, mut open: impl FnMut(Voxel) -> bool) -> Vec<Voxel> {
+    let mut seen: HashSet<Voxel> = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(v) = queue.pop_front() {
+        for n in neighbors(v) {
+            if !seen.contains(&n) && open(n) {
+                seen.insert(n);
+                queue.push_back(n);
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+impl Droplet {
+    pub fn new(cubes: impl IntoIterator<Item = Voxel>) -> Droplet {
+        let cubes: HashSet<Voxel> = cubes.into_iter().collect();
+        let fold = |pick: fn(i64, i64) -> i64, init: i64| {
+            cubes.iter().fold((init, init, init), |acc, c| {
+                (pick(acc.0, c.0), pick(acc.1, c.1), pick(acc.2, c.2))
+            })
+        };
+        let (min, max) = if cubes.is_empty() {
+            ((0, 0, 0), (-1, -1, -1))
+        } else {
+            (fold(i64::min, i64::MAX), fold(i64::max, i64::MIN))
+        };
+
+        let mut droplet = Droplet {
+            cubes,
+            outside: HashSet::new(),
+            min,
+            max,
+        };
+        let start = (min.0 - 1, min.1 - 1, min.2 - 1);
+        droplet.outside = flood(start, |v| {
+            droplet.in_bounds(v, 1) && !droplet.cubes.contains(&v)
+        })
+        .into_iter()
+        .collect();
+        droplet
+    }
+
+    fn in_bounds(&self, (x, y, z): Voxel, margin: i64) -> bool {
+        (self.min.0 - margin..=self.max.0 + margin).contains(&x)
+            && (self.min.1 - margin..=self.max.1 + margin).contains(&y)
+            && (self.min.2 - margin..=self.max.2 + margin).contains(&z)
+    }
+
+    pub fn contains(&self, v: Voxel) -> bool {
+        self.cubes.contains(&v)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cubes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cubes.is_empty()
+    }
+
+    /// Smallest and largest corner of the bounding box, inclusive.
+    pub fn bounds(&self) -> (Voxel, Voxel) {
+        (self.min, self.max)
+    }
+
+    /// Whether an empty voxel is open to the outside rather than sealed in a pocket.
+    pub fn is_outside(&self, v: Voxel) -> bool {
+        !self.cubes.contains(&v) && (!self.in_bounds(v, 0) || self.outside.contains(&v))
+    }
+
+    /// Every cube face not touching another cube.
+    pub fn faces(&self) -> impl Iterator<Item = Face> + '_ {
+        self.cubes.iter().flat_map(move |&cube| {
+            neighbors(cube)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, n)| !self.cubes.contains(n))
+                .map(move |(dir, _)| Face { cube, dir })
+        })
+    }
+
+    /// Only the faces that can be seen from outside.
+    pub fn exterior_faces(&self) -> impl Iterator<Item = Face> + '_ {
+        self.faces().filter(|f| {
+            let (dx, dy, dz) = f.normal();
+            self.is_outside((f.cube.0 + dx, f.cube.1 + dy, f.cube.2 + dz))
+        })
+    }
+
+    pub fn surface_area(&self) -> usize {
+        self.faces().count()
+    }
+
+    pub fn exterior_area(&self) -> usize {
+        self.exterior_faces().count()
+    }
+
+    pub fn components(&self) -> Vec<Component> {
+        let mut unvisited = self.cubes.clone();
+        let mut results = vec![];
+        while let Some(&start) = unvisited.iter().next() {
+            let mut cubes = flood(start, |v| self.cubes.contains(&v));
+            for c in &cubes {
+                unvisited.remove(c);
+            }
+            cubes.sort_unstable();
+
+            let (mut exterior_area, mut interior_area) = (0, 0);
+            for n in cubes.iter().flat_map(|&c| neighbors(c)) {
+                if self.cubes.contains(&n) {
+                    continue;
+                }
+                if self.is_outside(n) {
+                    exterior_area += 1;
+                } else {
+                    interior_area += 1;
+                }
+            }
+            results.push(Component {
+                cubes,
+                exterior_area,
+                interior_area,
+            });
+        }
+        results.sort_by(|a, b| a.cubes[0].cmp(&b.cubes[0]));
+        results
+    }
+
+    pub fn air_pockets(&self) -> Vec<AirPocket> {
+        let mut sealed: HashSet<Voxel> = HashSet::new();
+        for x in self.min.0..=self.max.0 {
+            for y in self.min.1..=self.max.1 {
+                for z in self.min.2..=self.max.2 {
+                    if !self.is_outside((x, y, z)) && !self.cubes.contains(&(x, y, z)) {
+                        sealed.insert((x, y, z));
+                    }
+                }
+            }
+        }
+
+        let mut results = vec![];
+        while let Some(&start) = sealed.iter().next() {
+            let mut cells = flood(start, |v| sealed.contains(&v));
+            for c in &cells {
+                sealed.remove(c);
+            }
+            cells.sort_unstable();
+            results.push(AirPocket { cells });
+        }
+        results.sort_by(|a, b| a.cells[0].cmp(&b.cells[0]));
+        results
+    }
+
+    /// Wavefront OBJ of the surface, one quad per face. Shared corners are written once.
+    pub fn to_obj(&self, exterior_only: bool) -> String {
+        let mut out = String::from("# day18 droplet\n");
+        let mut index: HashMap<Voxel, usize> = HashMap::new();
+        let mut faces = String::new();
+        for face in self.mesh_faces(exterior_only) {
+            let ids = face.corners().map(|c| {
+                let next = index.len() + 1;
+                *index.entry(c).or_insert_with(|| {
+                    writeln!(out, "v {} {} {}", c.0, c.1, c.2).unwrap();
+                    next
+                })
+            });
+            writeln!(faces, "f {} {} {} {}", ids[0], ids[1], ids[2], ids[3]).unwrap();
+        }
+        out.push_str(&faces);
+        out
+    }
+
+    /// ASCII STL of the surface, two triangles per face.
+    pub fn to_stl(&self, exterior_only: bool) -> String {
+        let mut out = String::from("solid droplet\n");
+        for face in self.mesh_faces(exterior_only) {
+            let (nx, ny, nz) = face.normal();
+            let [a, b, c, d] = face.corners();
+            for tri in [[a, b, c], [a, c, d]] {
+                writeln!(out, "  facet normal {} {} {}", nx, ny, nz).unwrap();
+                out.push_str("    outer loop\n");
+                for v in tri {
+                    writeln!(out, "      vertex {} {} {}", v.0, v.1, v.2).unwrap();
+                }
+                out.push_str("    endloop\n  endfacet\n");
+            }
+        }
+        out.push_str("endsolid droplet\n");
+        out
+    }
+
+    /// Faces in a fixed order, so exports are reproducible.
+    fn mesh_faces(&self, exterior_only: bool) -> Vec<Face> {
+        let mut faces: Vec<Face> = if exterior_only {
+            self.exterior_faces().collect()
+        } else {
+            self.faces().collect()
+        };
+        faces.sort_unstable_by_key(|f| (f.cube, f.dir));
+        faces
+    }
+}
+
+/// Cubes packed one bit per voxel over their bounding box plus a one-voxel empty margin.
+///
+/// Each `(y, z)` row of voxels along x is a run of `u64` words, so a whole row can be shifted,
+/// masked and counted at once. Suited to dense droplets like the puzzle's, where a `HashSet`
+/// spends most of its time hashing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoxelGrid {
+    /// World position of bit 0 of row 0, which is in the margin.
+    origin: Voxel,
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    words: usize,
+    solid: Vec<u64>,
+}
+
+impl VoxelGrid {
+    pub fn new(cubes: &[Voxel]) -> VoxelGrid {
+        let droplet_min = cubes.iter().fold((i64::MAX, i64::MAX, i64::MAX), |a, c| {
+            (a.0.min(c.0), a.1.min(c.1), a.2.min(c.2))
+        });
+        let droplet_max = cubes.iter().fold((i64::MIN, i64::MIN, i64::MIN), |a, c| {
+            (a.0.max(c.0), a.1.max(c.1), a.2.max(c.2))
+        });
+        let (origin, (nx, ny, nz)) = if cubes.is_empty() {
+            ((0, 0, 0), (1, 1, 1))
+        } else {
+            let extent = |lo: i64, hi: i64| (hi - lo + 3) as usize;
+            (
+                (droplet_min.0 - 1, droplet_min.1 - 1, droplet_min.2 - 1),
+                (
+                    extent(droplet_min.0, droplet_max.0),
+                    extent(droplet_min.1, droplet_max.1),
+                    extent(droplet_min.2, droplet_max.2),
+                ),
+            )
+        };
+
+        let words = nx.div_ceil(64);
+        let mut grid = VoxelGrid {
+            origin,
+            nx,
+            ny,
+            nz,
+            words,
+            solid: vec![0; words * ny * nz],
+        };
+        for &c in cubes {
+            let (x, row) = grid.locate(c).unwrap();
+            grid.solid[row * words + x / 64] |= 1 << (x % 64);
+        }
+        grid
+    }
+
+    /// Bit index along x and row number of a voxel, if it is inside the grid.
+    fn locate(&self, (x, y, z): Voxel) -> Option<(usize, usize)> {
+        let x = usize::try_from(x - self.origin.0).ok()?;
+        let y = usize::try_from(y - self.origin.1).ok()?;
+        let z = usize::try_from(z - self.origin.2).ok()?;
+        (x < self.nx && y < self.ny && z < self.nz).then_some((x, y + self.ny * z))
+    }
+
+    pub fn contains(&self, v: Voxel) -> bool {
+        self.locate(v)
+            .is_some_and(|(x, row)| self.solid[row * self.words + x / 64] >> (x % 64) & 1 == 1)
+    }
+
+    fn row<'a>(&self, bits: &'a [u64], row: usize) -> &'a [u64] {
+        &bits[row * self.words..(row + 1) * self.words]
+    }
+
+    /// Rows sharing a face with `row`, all of which exist thanks to the margin except at the
+    /// grid's own edge.
+    fn adjacent_rows(&self, row: usize) -> impl Iterator<Item = usize> {
+        let (y, z) = (row % self.ny, row / self.ny);
+        let ny = self.ny;
+        [
+            (y > 0).then(|| row - 1),
+            (y + 1 < self.ny).then_some(row + 1),
+            (z > 0).then(|| row - ny),
+            (z + 1 < self.nz).then_some(row + ny),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Mask of the bits in the last word of a row that are real voxels.
+    fn last_word_mask(&self) -> u64 {
+        match self.nx % 64 {
+            0 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Word `i` of `bits` shifted one voxel towards +x (`up`) or -x, carrying across words.
+    fn shifted(&self, bits: &[u64], i: usize, up: bool) -> u64 {
+        if up {
+            let carry = if i > 0 { bits[i - 1] >> 63 } else { 0 };
+            bits[i] << 1 | carry
+        } else {
+            let carry = bits.get(i + 1).map_or(0, |w| w << 63);
+            bits[i] >> 1 | carry
+        }
+    }
+
+    /// Faces between a cube and any voxel in `air`, which must not overlap the cubes.
+    fn faces_against(&self, air: &[u64]) -> usize {
+        let mut count = 0;
+        for row in 0..self.ny * self.nz {
+            let (a, s) = (self.row(air, row), self.row(&self.solid, row));
+            for (i, a) in a.iter().enumerate() {
+                count += (a & self.shifted(s, i, true)).count_ones() as usize;
+                count += (a & self.shifted(s, i, false)).count_ones() as usize;
+            }
+            for other in self.adjacent_rows(row) {
+                let s = self.row(&self.solid, other);
+                count += a
+                    .iter()
+                    .zip(s)
+                    .map(|(a, s)| (a & s).count_ones() as usize)
+                    .sum::<usize>();
+            }
+        }
+        count
+    }
+
+    pub fn surface_area(&self) -> usize {
+        let mask = self.last_word_mask();
+        let air: Vec<u64> = self
+            .solid
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                if i % self.words == self.words - 1 {
+                    !s & mask
+                } else {
+                    !s
+                }
+            })
+            .collect();
+        self.faces_against(&air)
+    }
+
+    /// Bits for every empty voxel connected to the margin.
+    ///
+    /// A scanline fill over whole rows: a row takes in what its four neighbouring rows have
+    /// reached, then spreads along itself with shifts until it stops growing. Any row that
+    /// changed puts its neighbours back on the work list.
+    pub fn outside(&self) -> Vec<u64> {
+        let rows = self.ny * self.nz;
+        let mask = self.last_word_mask();
+        let mut outside = vec![0u64; self.solid.len()];
+        // The corner of the margin is always empty.
+        outside[0] = 1;
+        let mut queued = vec![false; rows];
+        let mut work: VecDeque<usize> = VecDeque::from([0]);
+        queued[0] = true;
+
+        let mut next = vec![0u64; self.words];
+        while let Some(row) = work.pop_front() {
+            queued[row] = false;
+            next.copy_from_slice(self.row(&outside, row));
+            for other in self.adjacent_rows(row) {
+                for (n, o) in next.iter_mut().zip(self.row(&outside, other)) {
+                    *n |= o;
+                }
+            }
+            let solid = self.row(&self.solid, row);
+            for (i, n) in next.iter_mut().enumerate() {
+                *n &= !solid[i];
+            }
+            loop {
+                let mut grew = false;
+                for i in 0..self.words {
+                    let mut spread =
+                        next[i] | self.shifted(&next, i, true) | self.shifted(&next, i, false);
+                    spread &= !solid[i];
+                    if i == self.words - 1 {
+                        spread &= mask;
+                    }
+                    grew |= spread != next[i];
+                    next[i] = spread;
+                }
+                if !grew {
+                    break;
+                }
+            }
+
+            let current = &mut outside[row * self.words..(row + 1) * self.words];
+            if current != next.as_slice() {
+                current.copy_from_slice(&next);
+                for other in self.adjacent_rows(row) {
+                    if !queued[other] {
+                        queued[other] = true;
+                        work.push_back(other);
+                    }
+                }
+            }
+        }
+        outside
+    }
+
+    pub fn exterior_area(&self) -> usize {
+        self.faces_against(&self.outside())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::lcg;
+
+    const EXAMPLE: [Voxel; 13] = [
+        (2, 2, 2),
+        (1, 2, 2),
+        (3, 2, 2),
+        (2, 1, 2),
+        (2, 3, 2),
+        (2, 2, 1),
+        (2, 2, 3),
+        (2, 2, 4),
+        (2, 2, 6),
+        (1, 2, 5),
+        (3, 2, 5),
+        (2, 1, 5),
+        (2, 3, 5),
+    ];
+
+    #[test]
+    fn test_example_droplet() {
+        let droplet = Droplet::new(EXAMPLE);
+        assert_eq!(droplet.surface_area(), 64);
+        assert_eq!(droplet.exterior_area(), 58);
+        assert!(droplet.is_outside((0, 0, 0)));
+        assert!(!droplet.is_outside((2, 2, 5)));
+
+        let pockets = droplet.air_pockets();
+        assert_eq!(pockets.len(), 1);
+        assert_eq!(pockets[0].cells, vec![(2, 2, 5)]);
+        assert_eq!(pockets[0].volume(), 1);
+
+        let components = droplet.components();
+        assert_eq!(components.len(), 6);
+        assert_eq!(components.iter().map(|c| c.cubes.len()).sum::<usize>(), 13);
+        let main = components.iter().find(|c| c.cubes.len() == 8).unwrap();
+        assert_eq!((main.exterior_area, main.interior_area), (33, 1));
+        assert!(components.iter().all(|c| c.interior_area == 1));
+        assert_eq!(
+            components.iter().map(|c| c.exterior_area).sum::<usize>(),
+            58
+        );
+    }
+
+    #[test]
+    fn test_hollow_cube() {
+        // A 3x3x3 shell around one empty cell, and a 5x5x5 shell around a 3x3x3 pocket.
+        let shell = |lo: i64, hi: i64| {
+            let side = lo..=hi;
+            itertools::iproduct!(side.clone(), side.clone(), side)
+                .filter(move |&(x, y, z)| [x, y, z].iter().any(|&c| c == lo || c == hi))
+                .collect::<Vec<_>>()
+        };
+        let small = Droplet::new(shell(0, 2));
+        assert_eq!(small.exterior_area(), 54);
+        assert_eq!(small.surface_area(), 60);
+        assert_eq!(small.components()[0].interior_area, 6);
+
+        let big = Droplet::new(shell(10, 14).into_iter().chain(shell(0, 2)));
+        let volumes: Vec<usize> = big.air_pockets().iter().map(|p| p.volume()).collect();
+        assert_eq!(volumes, vec![1, 27]);
+        assert_eq!(big.components().len(), 2);
+    }
+
+    #[test]
+    fn test_mesh_export() {
+        let cube = Droplet::new([(0, 0, 0)]);
+        let obj = cube.to_obj(true);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 6);
+
+        // Every face's winding agrees with its normal.
+        for face in cube.faces() {
+            let [a, b, c, _] = face.corners();
+            let (u, v) = (
+                (b.0 - a.0, b.1 - a.1, b.2 - a.2),
+                (c.0 - a.0, c.1 - a.1, c.2 - a.2),
+            );
+            let cross = (
+                u.1 * v.2 - u.2 * v.1,
+                u.2 * v.0 - u.0 * v.2,
+                u.0 * v.1 - u.1 * v.0,
+            );
+            assert_eq!(cross, face.normal());
+        }
+
+        let stl = Droplet::new(EXAMPLE).to_stl(true);
+        assert!(stl.starts_with("solid droplet\n"));
+        assert!(stl.ends_with("endsolid droplet\n"));
+        assert_eq!(stl.matches("facet normal").count(), 2 * 58);
+    }
+
+    #[test]
+    fn test_grid_matches_droplet() {
+        let grid = VoxelGrid::new(&EXAMPLE);
+        assert_eq!(grid.surface_area(), 64);
+        assert_eq!(grid.exterior_area(), 58);
+        assert!(grid.contains((2, 2, 6)) && !grid.contains((2, 2, 5)));
+        assert!(!grid.contains((100, 0, 0)));
+        assert_eq!(VoxelGrid::new(&[]).exterior_area(), 0);
+
+        // Random blobs, some wider than one word along x.
+        let mut rand = lcg(12345);
+        for width in [5, 20, 70, 130] {
+            let cubes: Vec<Voxel> = (0..width * 40)
+                .map(|_| {
+                    let (x, y, z) = (rand(width), rand(12), rand(12));
+                    (x as i64 - 3, y as i64, z as i64 - 20)
+                })
+                .collect();
+            let droplet = Droplet::new(cubes.iter().copied());
+            let grid = VoxelGrid::new(&cubes);
+            assert_eq!(
+                grid.surface_area(),
+                droplet.surface_area(),
+                "width {}",
+                width
+            );
+            assert_eq!(
+                grid.exterior_area(),
+                droplet.exterior_area(),
+                "width {}",
+                width
+            );
+        }
+    }
+}
@@ -2,8 +2,18 @@ use std::cmp::max;
 
 use chumsky::prelude::*;
 
+use aoc_common::params::{self, WithParams};
+use aoc_common::puzzle_params;
 use aoc_zen_runner_macros::{aoc, generator, solution, solver};
 
+puzzle_params! {
+    pub struct Params {
+        red_max: u8 = 12,
+        green_max: u8 = 13,
+        blue_max: u8 = 14,
+    }
+}
+
 #[aoc(2023, day2)]
 pub mod solutions {
     use super::*;
@@ -26,10 +36,8 @@ pub mod solutions {
 
     #[solution(part1, draft_soln)]
     pub fn part1_draft(input: &str) -> u32 {
+        let (params, input): (Params, &str) = params::load(input);
         let mut tally = 0;
-        const RED_MAX: u8 = 12;
-        const GREEN_MAX: u8 = 13;
-        const BLUE_MAX: u8 = 14;
         let mut count_val = 0;
         let mut check_val = 0;
         let mut state = Seeking::Start;
@@ -62,13 +70,13 @@ pub mod solutions {
                 Seeking::Color => {
                     match c {
                         b'r' => {
-                            check_val = RED_MAX;
+                            check_val = params.red_max;
                         }
                         b'g' => {
-                            check_val = GREEN_MAX;
+                            check_val = params.green_max;
                         }
                         b'b' => {
-                            check_val = BLUE_MAX;
+                            check_val = params.blue_max;
                         }
                         b' ' => {
                             continue;
@@ -198,14 +206,15 @@ pub mod solutions {
     }
 
     #[generator(chumsky)]
-    pub fn chumsky_parser(input: &str) -> Vec<Game> {
-        parser().parse(input).unwrap()
+    pub fn chumsky_parser(input: &str) -> WithParams<Vec<Game>, Params> {
+        params::parse_with(input, |rest| parser().parse(rest).unwrap())
     }
 
     #[solver(part1, chumsky)]
-    pub fn part1_chumsky(input: Vec<Game>) -> u32 {
-        input.into_iter()
-            .filter(|Game(_, grb)| grb.red <= 12 && grb.green <= 13 && grb.blue <= 14 )
+    pub fn part1_chumsky(input: WithParams<Vec<Game>, Params>) -> u32 {
+        let limits = input.params;
+        input.data.into_iter()
+            .filter(|Game(_, grb)| grb.red <= limits.red_max && grb.green <= limits.green_max && grb.blue <= limits.blue_max)
             .map(|Game(n, _)| n)
             .sum()
     }
@@ -213,6 +222,7 @@ pub mod solutions {
     // ----------------------- Part 2 -----------------------
     #[solution(part2, draft_soln)]
     pub fn part2_draft(input: &str) -> u32 {
+        let (_, input): (Params, &str) = params::load(input);
         let mut tally = 0;
 
         let mut count_val = 0;
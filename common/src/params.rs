@@ -0,0 +1,324 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Environment variable the runner reads overrides from, as `key=value` pairs separated by
+/// whitespace. Every day sees the same assignments, so each day skips keys it doesn't have.
+pub const ENV_VAR: &str = "AOC_PARAMS";
+
+/// Prefix for parameter lines at the very top of an input. Examples use these to carry the
+/// parameters the puzzle text gives for them, e.g. `#! target_row=10 max_coord=20`.
+pub const HEADER_PREFIX: &str = "#!";
+
+/// A day's tunable puzzle parameters. Usually declared with `puzzle_params!`.
+pub trait PuzzleParams: Default + Clone + 'static {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ParamError>;
+
+    /// Apply whitespace-separated `key=value` assignments.
+    fn apply(&mut self, assignments: &str) -> Result<(), ParamError> {
+        for assignment in assignments.split_whitespace() {
+            let Some((key, value)) = assignment.split_once('=') else {
+                return Err(ParamError::Malformed(assignment.to_owned()));
+            };
+            self.set(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Like `apply`, but skip keys these parameters don't have, for assignments meant for
+    /// several days at once. Bad values for known keys are still errors.
+    fn apply_shared(&mut self, assignments: &str) -> Result<(), ParamError> {
+        for assignment in assignments.split_whitespace() {
+            match self.apply(assignment) {
+                Err(ParamError::UnknownKey(_)) => {}
+                result => result?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError {
+    Malformed(String),
+    UnknownKey(String),
+    InvalidValue { key: String, value: String },
+}
+
+impl Display for ParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamError::Malformed(s) => write!(f, "expected key=value, found {:?}", s),
+            ParamError::UnknownKey(key) => write!(f, "unknown parameter {:?}", key),
+            ParamError::InvalidValue { key, value } => {
+                write!(f, "invalid value {:?} for parameter {:?}", value, key)
+            }
+        }
+    }
+}
+
+impl Error for ParamError {}
+
+/// Values a parameter can hold. Lists are comma-separated.
+pub trait ParamValue: Sized {
+    fn parse_param(s: &str) -> Option<Self>;
+}
+
+macro_rules! impl_param_value {
+    ($($t:ty),*) => {
+        $(
+            impl ParamValue for $t {
+                fn parse_param(s: &str) -> Option<Self> {
+                    s.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_param_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, bool, String);
+
+impl<T: ParamValue> ParamValue for Vec<T> {
+    fn parse_param(s: &str) -> Option<Self> {
+        s.split(',')
+            .filter(|v| !v.is_empty())
+            .map(T::parse_param)
+            .collect()
+    }
+}
+
+pub fn parse_value<T: ParamValue>(key: &str, value: &str) -> Result<T, ParamError> {
+    T::parse_param(value).ok_or_else(|| ParamError::InvalidValue {
+        key: key.to_owned(),
+        value: value.to_owned(),
+    })
+}
+
+/// Declare a parameter struct with a default for every field.
+///
+/// ```
+/// aoc_common::puzzle_params! {
+///     pub struct Params {
+///         rounds: u32 = 20,
+///         breakpoints: Vec<i32> = vec![20, 60],
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! puzzle_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field:ident : $ty:ty = $default:expr),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $name {
+            $(pub $field: $ty),*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name {
+                    $($field: $default),*
+                }
+            }
+        }
+
+        impl $crate::params::PuzzleParams for $name {
+            fn set(&mut self, key: &str, value: &str) -> Result<(), $crate::params::ParamError> {
+                match key {
+                    $(stringify!($field) => {
+                        self.$field = $crate::params::parse_value(key, value)?;
+                        Ok(())
+                    })*
+                    _ => Err($crate::params::ParamError::UnknownKey(key.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+/// Generator output bundled with the parameters the solvers should use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithParams<T, P> {
+    pub data: T,
+    pub params: P,
+}
+
+thread_local! {
+    static SCOPED: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+struct RestoreScope {
+    id: TypeId,
+    previous: Option<Box<dyn Any>>,
+}
+
+impl Drop for RestoreScope {
+    fn drop(&mut self) {
+        SCOPED.with(|s| {
+            let mut s = s.borrow_mut();
+            match self.previous.take() {
+                Some(p) => s.insert(self.id, p),
+                None => s.remove(&self.id),
+            };
+        });
+    }
+}
+
+/// Run `f` with `params` in place of `P::default()` for every `load` on this thread.
+pub fn with_params<P: PuzzleParams, R>(params: P, f: impl FnOnce() -> R) -> R {
+    let id = TypeId::of::<P>();
+    let previous = SCOPED.with(|s| s.borrow_mut().insert(id, Box::new(params)));
+    let _restore = RestoreScope { id, previous };
+    f()
+}
+
+/// Split leading `#!` parameter lines off an input. Inputs without a header come back as-is.
+pub fn split_header(input: &str) -> (Vec<&str>, &str) {
+    let mut lines = Vec::new();
+    let mut rest = input;
+    while let Some(body) = rest.trim_start().strip_prefix(HEADER_PREFIX) {
+        let (line, next) = body.split_once('\n').unwrap_or((body, ""));
+        lines.push(line);
+        rest = next;
+    }
+    if lines.is_empty() {
+        (lines, input)
+    } else {
+        (lines, rest)
+    }
+}
+
+/// Resolve parameters for one run. Later sources win: `P::default()` (or the value given to
+/// `with_params`), then `AOC_PARAMS`, then header lines in the input. Returns the input with
+/// the header removed.
+pub fn try_load<P: PuzzleParams>(input: &str) -> Result<(P, &str), ParamError> {
+    let mut params = SCOPED
+        .with(|s| {
+            s.borrow()
+                .get(&TypeId::of::<P>())
+                .and_then(|p| p.downcast_ref::<P>())
+                .cloned()
+        })
+        .unwrap_or_default();
+
+    if let Ok(assignments) = std::env::var(ENV_VAR) {
+        params.apply_shared(&assignments)?;
+    }
+
+    let (header, rest) = split_header(input);
+    for line in header {
+        params.apply(line)?;
+    }
+
+    Ok((params, rest))
+}
+
+pub fn load<P: PuzzleParams>(input: &str) -> (P, &str) {
+    try_load(input).unwrap_or_else(|e| panic!("Invalid puzzle parameters: {}", e))
+}
+
+/// Load parameters and run a generator over the rest of the input.
+pub fn parse_with<P: PuzzleParams, T>(
+    input: &str,
+    generator: impl FnOnce(&str) -> T,
+) -> WithParams<T, P> {
+    let (params, rest) = load(input);
+    WithParams {
+        data: generator(rest),
+        params,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::puzzle_params! {
+        struct TestParams {
+            rounds: u32 = 20,
+            limit: i64 = -4,
+            breakpoints: Vec<i32> = vec![20, 60],
+        }
+    }
+
+    #[test]
+    fn test_defaults_and_header() {
+        let (params, rest) = load::<TestParams>("1\n2\n");
+        assert_eq!(params, TestParams::default());
+        assert_eq!(rest, "1\n2\n");
+
+        let (params, rest) =
+            load::<TestParams>("\n#! rounds=3 limit=-9\n#! breakpoints=1,2,3\n1\n2\n");
+        assert_eq!(params.rounds, 3);
+        assert_eq!(params.limit, -9);
+        assert_eq!(params.breakpoints, vec![1, 2, 3]);
+        assert_eq!(rest, "1\n2\n");
+    }
+
+    #[test]
+    fn test_scoped_override() {
+        let custom = TestParams {
+            rounds: 7,
+            ..Default::default()
+        };
+        let (inner, _) = with_params(custom, || load::<TestParams>("#! limit=1\n"));
+        assert_eq!((inner.rounds, inner.limit), (7, 1));
+
+        let (outer, _) = load::<TestParams>("");
+        assert_eq!(outer.rounds, 20);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut params = TestParams::default();
+        assert_eq!(
+            params.apply("speed=3"),
+            Err(ParamError::UnknownKey("speed".to_owned()))
+        );
+        assert_eq!(
+            params.apply("rounds=-3"),
+            Err(ParamError::InvalidValue {
+                key: "rounds".to_owned(),
+                value: "-3".to_owned()
+            })
+        );
+        assert_eq!(
+            params.apply("rounds"),
+            Err(ParamError::Malformed("rounds".to_owned()))
+        );
+        assert!(try_load::<TestParams>("#! breakpoints=1,x\n").is_err());
+        assert!(try_load::<TestParams>("#! speed=3\n").is_err());
+    }
+
+    #[test]
+    fn test_shared_assignments() {
+        let mut params = TestParams::default();
+        params
+            .apply_shared("max_coord=20 rounds=3 part1_crane=9001")
+            .unwrap();
+        assert_eq!(params.rounds, 3);
+        assert_eq!(
+            params.apply_shared("speed=3 limit=x"),
+            Err(ParamError::InvalidValue {
+                key: "limit".to_owned(),
+                value: "x".to_owned()
+            })
+        );
+        assert_eq!(
+            params.apply_shared("rounds"),
+            Err(ParamError::Malformed("rounds".to_owned()))
+        );
+
+        // Other tests load at the same time, so only set keys `TestParams` doesn't have.
+        std::env::set_var(ENV_VAR, "max_coord=20 part1_crane=9001");
+        let loaded = try_load::<TestParams>("1\n");
+        std::env::remove_var(ENV_VAR);
+        assert_eq!(loaded, Ok((TestParams::default(), "1\n")));
+    }
+}